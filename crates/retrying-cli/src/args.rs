@@ -0,0 +1,159 @@
+/// Command of the tool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Command {
+    Schedule,
+    Envs,
+    Run,
+    Help,
+}
+
+/// Parsed command line arguments.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Args {
+    pub(crate) command: Command,
+    /// configuration options in the order of arguments, so the last option wins like in `retry` macros.
+    options: Vec<String>,
+    pub(crate) chart: bool,
+    pub(crate) attempts: u32,
+    /// command with arguments for `run` command.
+    pub(crate) program: Vec<String>,
+}
+
+impl Args {
+    const DEFAULT_ATTEMPTS: u32 = 20;
+
+    pub(crate) fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Args, String> {
+        let command = match args.next().as_deref() {
+            Some("schedule") => Command::Schedule,
+            Some("envs") => Command::Envs,
+            Some("run") => Command::Run,
+            Some("-h") | Some("--help") | Some("help") | None => Command::Help,
+            Some(unknown) => return Err(format!("Unknown command `{}`", unknown)),
+        };
+
+        let mut parsed = Args {
+            command,
+            options: Vec::new(),
+            chart: false,
+            attempts: Self::DEFAULT_ATTEMPTS,
+            program: Vec::new(),
+        };
+        let mut positional = Vec::new();
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .ok_or_else(|| format!("Option `{}` requires value", name))
+            };
+            match arg.as_str() {
+                "--stop" => parsed.options.push(format!("stop={}", value(&arg)?)),
+                "--wait" => parsed.options.push(format!("wait={}", value(&arg)?)),
+                "--envs-prefix" => parsed
+                    .options
+                    .push(format!("envs_prefix={:?}", value(&arg)?)),
+                "--attempts" => {
+                    let attempts = value(&arg)?;
+                    parsed.attempts = attempts
+                        .parse()
+                        .ok()
+                        .filter(|attempts| *attempts > 0)
+                        .ok_or_else(|| {
+                            format!("`--attempts` should be positive number, got `{}`", attempts)
+                        })?;
+                }
+                "--chart" => parsed.chart = true,
+                "-h" | "--help" => parsed.command = Command::Help,
+                "--" => {
+                    parsed.program = args.by_ref().collect();
+                    break;
+                }
+                option if option.starts_with("--") => {
+                    return Err(format!("Unknown option `{}`", option))
+                }
+                config => positional.push(config.to_string()),
+            }
+        }
+        // positional configuration goes first, so `--stop` and `--wait` options override it
+        parsed.options.splice(0..0, positional);
+
+        if parsed.command == Command::Run && parsed.program.is_empty() {
+            return Err(String::from(
+                "`run` command requires command after `--`, for example `retrying-cli run --stop 'attempts(3)' -- curl localhost`",
+            ));
+        }
+        Ok(parsed)
+    }
+
+    /// returns configuration in syntax of `retry` macros, options have priority over positional configuration.
+    pub(crate) fn config(&self) -> String {
+        self.options
+            .iter()
+            .filter(|option| !option.trim().is_empty())
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        Args::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let args = parse(&[
+            "run",
+            "--stop",
+            "attempts(5)",
+            "wait=fixed(1)",
+            "--envs-prefix",
+            "app",
+            "--",
+            "curl",
+            "--fail",
+            "localhost",
+        ])
+        .unwrap();
+
+        assert_eq!(args.command, Command::Run);
+        assert_eq!(
+            args.config(),
+            "wait=fixed(1),stop=attempts(5),envs_prefix=\"app\""
+        );
+        assert_eq!(args.program, vec!["curl", "--fail", "localhost"]);
+
+        let args = parse(&["schedule", "--chart", "--attempts", "5", "stop=attempts(3)"]).unwrap();
+        assert_eq!(args.command, Command::Schedule);
+        assert!(args.chart);
+        assert_eq!(args.attempts, 5);
+        assert_eq!(args.config(), "stop=attempts(3)");
+
+        let args = parse(&[
+            "schedule",
+            "stop=attempts(3)",
+            "--wait",
+            "fixed(2)",
+            "wait=fixed(1),stop=attempts(4)",
+        ])
+        .unwrap();
+        assert_eq!(
+            args.config(),
+            "stop=attempts(3),wait=fixed(1),stop=attempts(4),wait=fixed(2)"
+        );
+
+        assert_eq!(parse(&[]).unwrap().command, Command::Help);
+        assert_eq!(parse(&["envs", "--help"]).unwrap().command, Command::Help);
+    }
+
+    #[test]
+    fn test_parse_wrong_args() {
+        assert!(parse(&["simulate"]).is_err());
+        assert!(parse(&["schedule", "--stop"]).is_err());
+        assert!(parse(&["schedule", "--attempts", "0"]).is_err());
+        assert!(parse(&["schedule", "--verbose"]).is_err());
+        assert!(parse(&["run", "--stop", "attempts(3)"]).is_err());
+    }
+}
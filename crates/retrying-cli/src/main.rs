@@ -0,0 +1,228 @@
+//! Command-line tool to test and visualize configurations of `retrying` library.
+
+mod args;
+mod output;
+mod run;
+
+use crate::args::{Args, Command};
+use retrying::envs::check_env;
+use retrying::stop::StopStrategy;
+use retrying::wait::WaitStrategy;
+use retrying::Policy;
+use retrying_config::{split_policy_path, RetryingConfig, StopConfig, WaitConfig};
+use std::process::ExitCode;
+
+/// exit code of wrong command line arguments or configuration.
+const USAGE_ERROR: u8 = 2;
+
+const USAGE: &str = "\
+retrying-cli - test and visualize configurations of `retrying` library
+
+USAGE:
+    retrying-cli schedule [OPTIONS] [CONFIG]          prints planned attempts of configuration
+    retrying-cli envs [OPTIONS] [CONFIG]              validates OS environment variables overriding configuration
+    retrying-cli run [OPTIONS] [CONFIG] -- <COMMAND>  runs command until it succeeds using configuration
+
+CONFIG uses syntax of `retry` macros, for example 'stop=(attempts(5)|duration(30)),wait=fixed(1)'.
+
+OPTIONS:
+    --stop <STOP>            stop option, for example 'attempts(5)'
+    --wait <WAIT>            wait option, for example 'exponential(multiplier=\"100ms\")'
+    --envs-prefix <PREFIX>   applies OS environment variables with prefix
+    --chart                  prints schedule as chart instead of table
+    --attempts <N>           maximal number of attempts in schedule (default 20)
+    -h, --help               prints this help";
+
+/// Stop and wait strategies of parsed configuration.
+#[derive(Debug, PartialEq)]
+struct Strategies {
+    stop: StopStrategy,
+    wait: Option<WaitStrategy>,
+    envs_prefix: Option<String>,
+}
+
+impl Strategies {
+    fn from_config(config: &str) -> Result<Strategies, String> {
+        let tokens = config
+            .parse::<proc_macro2::TokenStream>()
+            .map_err(|e| format!("Can't parse configuration `{}`. {}", config, e))?;
+        let (policy_path, tokens) = split_policy_path(tokens).map_err(|e| e.to_string())?;
+        let config = RetryingConfig::from_token_stream(tokens).map_err(|e| e.to_string())?;
+
+        let unsupported = [
+            ("retry", config.retry.is_some()),
+            ("policy", config.policy.is_some() || policy_path.is_some()),
+            ("clone_args", config.clone_args),
+            ("crate", config.crate_path.is_some()),
+            ("wrap_body", !config.wrap_body),
+            ("metrics_label", config.metrics_label.is_some()),
+            ("cancel", config.cancel.is_some()),
+            ("abort", config.abort.is_some()),
+            (
+                "stop=when(..)",
+                config
+                    .stop
+                    .as_ref()
+                    .map_or(false, |stop| stop.when.is_some()),
+            ),
+        ];
+        if let Some((option, _)) = unsupported.iter().find(|(_, used)| *used) {
+            return Err(format!(
+                "Configuration option `{}` is not supported by retrying-cli",
+                option
+            ));
+        }
+
+        let strategies = Strategies {
+            stop: config.stop.map_or(
+                StopStrategy {
+                    attempts: None,
+                    duration: None,
+                },
+                |StopConfig {
+                     attempts, duration, ..
+                 }| StopStrategy { attempts, duration },
+            ),
+            wait: config.wait.map(|wait| match wait {
+                WaitConfig::Fixed { duration } => WaitStrategy::Fixed { duration },
+                WaitConfig::Random { min, max } => WaitStrategy::Random { min, max },
+                WaitConfig::Exponential {
+                    multiplier,
+                    min,
+                    max,
+                    exp_base,
+                } => WaitStrategy::Exponential {
+                    multiplier,
+                    min,
+                    max,
+                    exp_base,
+                },
+            }),
+            envs_prefix: config.envs_prefix,
+        };
+        strategies.stop.validate().map_err(|e| e.msg)?;
+        if let Some(wait) = strategies.wait {
+            wait.validate().map_err(|e| e.msg)?;
+        }
+        Ok(strategies)
+    }
+
+    /// builds policy applying OS environment variables if prefix is configured. Errors of OS environment variables
+    /// are returned with the policy.
+    fn build(&self) -> (Policy, Vec<String>) {
+        match &self.envs_prefix {
+            Some(prefix) => {
+                let overrides = check_env(prefix, self.stop, self.wait);
+                (
+                    Policy::from_strategies(overrides.stop, Some(overrides.wait)),
+                    overrides.errors,
+                )
+            }
+            None => (Policy::from_strategies(self.stop, self.wait), Vec::new()),
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, USAGE);
+            return ExitCode::from(USAGE_ERROR);
+        }
+    };
+    if args.command == Command::Help {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    let strategies = match Strategies::from_config(&args.config()) {
+        Ok(strategies) => strategies,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            return ExitCode::from(USAGE_ERROR);
+        }
+    };
+    let (policy, env_errors) = strategies.build();
+
+    match args.command {
+        Command::Schedule => {
+            warn_env_errors(&env_errors);
+            let schedule = policy.simulate(args.attempts);
+            println!("{}", policy);
+            if args.chart {
+                print!("{}", output::chart(&schedule));
+            } else {
+                print!("{}", output::table(&schedule));
+            }
+            ExitCode::SUCCESS
+        }
+        Command::Envs => {
+            if strategies.envs_prefix.is_none() {
+                eprintln!("error: `envs` command requires `--envs-prefix` or `envs_prefix` configuration option");
+                return ExitCode::from(USAGE_ERROR);
+            }
+            println!("{}", policy);
+            if env_errors.is_empty() {
+                println!("OS environment variables are correct");
+                ExitCode::SUCCESS
+            } else {
+                for err in &env_errors {
+                    println!("error: {}", err);
+                }
+                ExitCode::FAILURE
+            }
+        }
+        Command::Run => {
+            warn_env_errors(&env_errors);
+            run::run(&policy, &args.program)
+        }
+        Command::Help => unreachable!("help is handled before parsing of configuration"),
+    }
+}
+
+fn warn_env_errors(errors: &[String]) {
+    for err in errors {
+        eprintln!("warning: {}", err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use retrying::Duration;
+
+    #[test]
+    fn test_strategies_from_config() {
+        let strategies = Strategies::from_config(
+            "stop=(attempts(5)|duration(30)),wait=random(min=1,max=\"2s\"),envs_prefix=\"app\"",
+        )
+        .unwrap();
+
+        assert_eq!(
+            strategies,
+            Strategies {
+                stop: StopStrategy {
+                    attempts: Some(5),
+                    duration: Some(Duration::from_secs(30)),
+                },
+                wait: Some(WaitStrategy::Random {
+                    min: Duration::from_secs(1),
+                    max: Duration::from_secs(2),
+                }),
+                envs_prefix: Some(String::from("app")),
+            }
+        );
+
+        let strategies = Strategies::from_config("").unwrap();
+        assert_eq!(strategies.stop.attempts, None);
+        assert_eq!(strategies.wait, None);
+
+        assert!(Strategies::from_config("stop=attempts(0)").is_err());
+        assert!(Strategies::from_config("stpo=attempts(1)").is_err());
+        assert!(Strategies::from_config("retry=if_errors(Error)").is_err());
+        assert!(Strategies::from_config("policy=DB_POLICY").is_err());
+        assert!(Strategies::from_config("stop=(attempts(3)|when(is_shutdown))").is_err());
+        assert!(Strategies::from_config("stop=\"unterminated").is_err());
+    }
+}
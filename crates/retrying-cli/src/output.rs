@@ -0,0 +1,164 @@
+use retrying::schedule::Schedule;
+use retrying::Duration;
+use std::fmt::Write;
+
+/// width of chart bars in characters.
+const CHART_WIDTH: usize = 50;
+
+/// formats schedule as table with attempt number, wait before the attempt and time of the attempt.
+pub(crate) fn table(schedule: &Schedule) -> String {
+    let rows = schedule
+        .attempts
+        .iter()
+        .map(|attempt| {
+            [
+                attempt.attempt.to_string(),
+                attempt.wait.to_string(),
+                attempt.at.to_string(),
+            ]
+        })
+        .collect::<Vec<_>>();
+    let header = [
+        String::from("attempt"),
+        String::from("wait"),
+        String::from("at"),
+    ];
+    let widths = rows
+        .iter()
+        .chain(Some(&header))
+        .fold([0; 3], |widths, row| {
+            [
+                widths[0].max(row[0].len()),
+                widths[1].max(row[1].len()),
+                widths[2].max(row[2].len()),
+            ]
+        });
+
+    let mut table = String::new();
+    for row in Some(&header).into_iter().chain(&rows) {
+        let _ = writeln!(
+            table,
+            "{:>w0$}  {:<w1$}  {}",
+            row[0],
+            row[1],
+            row[2],
+            w0 = widths[0],
+            w1 = widths[1]
+        );
+    }
+    table.push_str(&footer(schedule));
+    table
+}
+
+/// formats schedule as chart where each attempt is a bar up to the time of the attempt. Random part of the time
+/// is drawn with `~`.
+pub(crate) fn chart(schedule: &Schedule) -> String {
+    let total = schedule
+        .attempts
+        .last()
+        .map_or(Duration::ZERO, |attempt| attempt.at.max);
+    let scale = |duration: Duration| {
+        if total.is_zero() {
+            0
+        } else {
+            (duration.as_secs_f64() / total.as_secs_f64() * CHART_WIDTH as f64).round() as usize
+        }
+    };
+    let width = schedule
+        .attempts
+        .last()
+        .map_or(1, |attempt| attempt.attempt.to_string().len());
+
+    let mut chart = String::new();
+    for attempt in &schedule.attempts {
+        let min = scale(attempt.at.min);
+        let max = scale(attempt.at.max).max(min);
+        let _ = writeln!(
+            chart,
+            "{:>width$} |{}{}{} {}",
+            attempt.attempt,
+            "#".repeat(min),
+            "~".repeat(max - min),
+            " ".repeat(CHART_WIDTH - max),
+            attempt.at,
+            width = width
+        );
+    }
+    chart.push_str(&footer(schedule));
+    chart
+}
+
+fn footer(schedule: &Schedule) -> String {
+    match schedule.gives_up_after() {
+        Some(after) => format!("gives up after {}\n", after),
+        None => format!(
+            "keeps retrying after {} attempts\n",
+            schedule.attempts.len()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use retrying::stop::StopStrategy;
+    use retrying::wait::WaitStrategy;
+    use retrying::Policy;
+
+    #[test]
+    fn test_table() {
+        let policy = Policy::from_strategies(
+            StopStrategy {
+                attempts: Some(3),
+                duration: None,
+            },
+            Some(WaitStrategy::Random {
+                min: Duration::from_secs(1),
+                max: Duration::from_secs(3),
+            }),
+        );
+
+        assert_eq!(
+            table(&policy.simulate(20)),
+            "\
+attempt  wait                  at
+      1  0s                    0s
+      2  1s..3s (expected 2s)  1s..3s (expected 2s)
+      3  1s..3s (expected 2s)  2s..6s (expected 4s)
+gives up after 2s..6s (expected 4s)
+"
+        );
+    }
+
+    #[test]
+    fn test_chart() {
+        let policy = Policy::from_strategies(
+            StopStrategy {
+                attempts: None,
+                duration: None,
+            },
+            Some(WaitStrategy::Random {
+                min: Duration::from_secs(1),
+                max: Duration::from_secs(2),
+            }),
+        );
+
+        assert_eq!(
+            chart(&policy.simulate(3)),
+            format!(
+                "\
+1 |{} 0s
+2 |{}{}{} 1s..2s (expected 1.5s)
+3 |{}{} 2s..4s (expected 3s)
+keeps retrying after 3 attempts
+",
+                " ".repeat(50),
+                "#".repeat(13),
+                "~".repeat(12),
+                " ".repeat(25),
+                "#".repeat(25),
+                "~".repeat(25)
+            )
+        );
+    }
+}
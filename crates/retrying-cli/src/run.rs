@@ -0,0 +1,117 @@
+use retrying::{Outcome, Policy, Retryable};
+use std::io;
+use std::process::{Command, ExitCode, ExitStatus};
+
+/// exit code if command can't be started, the same as in shells.
+const NOT_FOUND: u8 = 127;
+
+/// Failed attempt to run command.
+enum Failure {
+    /// command exited with non-zero status, so it is retried.
+    Exit(ExitStatus),
+    /// command can't be started, so it isn't retried.
+    Spawn(io::Error),
+}
+
+/// Result of running command. Retried until the command exits successfully.
+struct Attempt(Result<(), Failure>);
+
+impl Retryable for Attempt {
+    type Ok = ();
+    type Error = Failure;
+
+    fn outcome(&self) -> Outcome<'_, Failure> {
+        match &self.0 {
+            Ok(()) => Outcome::Success,
+            Err(failure @ Failure::Exit(_)) => Outcome::Retry(failure),
+            Err(failure @ Failure::Spawn(_)) => Outcome::Abort(failure),
+        }
+    }
+
+    fn into_result(self) -> Result<(), Failure> {
+        self.0
+    }
+}
+
+/// returns non-zero exit code of failed command. Codes that don't fit into `u8` are reported as 1 and processes
+/// killed by signal are reported as 128 + signal number like in shells.
+fn exit_code(status: ExitStatus) -> u8 {
+    match status.code() {
+        Some(code) => u8::try_from(code)
+            .ok()
+            .filter(|code| *code != 0)
+            .unwrap_or(1),
+        None => signal_exit_code(status),
+    }
+}
+
+#[cfg(unix)]
+fn signal_exit_code(status: ExitStatus) -> u8 {
+    use std::os::unix::process::ExitStatusExt;
+
+    status
+        .signal()
+        .and_then(|signal| u8::try_from(128 + signal).ok())
+        .unwrap_or(1)
+}
+
+#[cfg(not(unix))]
+fn signal_exit_code(_status: ExitStatus) -> u8 {
+    1
+}
+
+/// runs `program` with arguments until it succeeds or `policy` stops retrying. Returns exit code of the last attempt.
+pub(crate) fn run(policy: &Policy, program: &[String]) -> ExitCode {
+    let mut attempt = 0;
+    let result = policy
+        .retry(|| {
+            attempt += 1;
+            let status = Command::new(&program[0]).args(&program[1..]).status();
+            Attempt(match status {
+                Ok(status) if status.success() => Ok(()),
+                Ok(status) => {
+                    eprintln!("retrying-cli: attempt {} failed with {}", attempt, status);
+                    Err(Failure::Exit(status))
+                }
+                Err(err) => Err(Failure::Spawn(err)),
+            })
+        })
+        .into_result();
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(Failure::Exit(status)) => {
+            eprintln!("retrying-cli: giving up after {} attempts", attempt);
+            ExitCode::from(exit_code(status))
+        }
+        Err(Failure::Spawn(err)) => {
+            eprintln!("retrying-cli: can't run `{}`. {}", program[0], err);
+            ExitCode::from(NOT_FOUND)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_exit_code() {
+        use std::os::unix::process::ExitStatusExt;
+
+        assert_eq!(exit_code(ExitStatus::from_raw(3 << 8)), 3);
+        assert_eq!(exit_code(ExitStatus::from_raw(255 << 8)), 255);
+        // killed by SIGKILL
+        assert_eq!(exit_code(ExitStatus::from_raw(9)), 137);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_exit_code() {
+        use std::os::windows::process::ExitStatusExt;
+
+        assert_eq!(exit_code(ExitStatus::from_raw(3)), 3);
+        assert_eq!(exit_code(ExitStatus::from_raw(256)), 1);
+    }
+}
@@ -0,0 +1,65 @@
+use std::process::Command;
+
+fn cli(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_retrying-cli"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_schedule() {
+    let output = cli(&["schedule", "--stop", "attempts(3)", "wait=fixed(1)"]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "\
+stops after 3 attempts, waits 1s between attempts
+attempt  wait  at
+      1  0s    0s
+      2  1s    1s
+      3  1s    2s
+gives up after 2s
+"
+    );
+}
+
+#[test]
+fn test_wrong_config() {
+    let output = cli(&["schedule", "stop=attempts(1),wiat=fixed(1)"]);
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unknown configuration option `wiat`"));
+}
+
+#[test]
+fn test_envs() {
+    let output = Command::new(env!("CARGO_BIN_EXE_retrying-cli"))
+        .args(["envs", "--envs-prefix", "cli_test", "stop=attempts(3)"])
+        .env("CLI_TEST__RETRYING__STOP__ATTEMPTS", "five")
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("stops after 3 attempts"));
+    assert!(stdout.contains("error: "));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_run() {
+    let output = cli(&["run", "--stop", "attempts(3)", "--", "sh", "-c", "exit 3"]);
+
+    assert_eq!(output.status.code(), Some(3));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("attempt 3 failed"));
+    assert!(stderr.contains("giving up after 3 attempts"));
+
+    let output = cli(&["run", "--stop", "attempts(3)", "--", "true"]);
+    assert!(output.status.success());
+
+    let output = cli(&["run", "--", "retrying-cli-missing-command"]);
+    assert_eq!(output.status.code(), Some(127));
+}
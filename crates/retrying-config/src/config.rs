@@ -0,0 +1,1402 @@
+use crate::duration::parse_duration;
+use crate::errors::{did_you_mean, RetryConfigurationError};
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
+use std::fmt::{self, Debug};
+use std::str::FromStr;
+use std::time::Duration;
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+
+// In syn 2.0 AttributeArgs was removed, so now we can use type alias to simplify syntaxis
+pub type AttributeArgs = syn::punctuated::Punctuated<syn::Meta, syn::Token![,]>;
+
+/// Value of `wait` configuration option.
+#[derive(Debug, PartialEq)]
+pub enum WaitConfig {
+    Fixed {
+        duration: Duration,
+    },
+    Random {
+        min: Duration,
+        max: Duration,
+    },
+    Exponential {
+        multiplier: Duration,
+        min: Duration,
+        max: Duration,
+        exp_base: u32,
+    },
+}
+
+impl WaitConfig {
+    const FIXED: &'static str = "fixed";
+    const RANDOM: &'static str = "random";
+    const EXPONENTIAL: &'static str = "exponential";
+    const MIN: &'static str = "min";
+    const MAX: &'static str = "max";
+    const EXP_BASE: &'static str = "exp_base";
+    const MULTIPLIER: &'static str = "multiplier";
+}
+
+/// Value of `stop` configuration option, `None` values are not limited.
+#[derive(Debug, PartialEq)]
+pub struct StopConfig {
+    pub attempts: Option<u32>,
+    pub duration: Option<Duration>,
+    /// path of function `fn(&RetryingContext) -> bool` that returns `true` to stop retrying.
+    pub when: Option<String>,
+}
+impl StopConfig {
+    const ATTEMPTS: &'static str = "attempts";
+    const DURATION: &'static str = "duration";
+    const WHEN: &'static str = "when";
+    const OPTIONS: [&'static str; 3] = [Self::ATTEMPTS, Self::DURATION, Self::WHEN];
+}
+
+/// Value of `retry` configuration option with paths of error variants.
+#[derive(Debug, PartialEq)]
+pub struct RetryConfig {
+    pub if_errors: Option<Vec<String>>,
+    pub if_not_errors: Option<Vec<String>>,
+}
+
+impl RetryConfig {
+    const IF_ERRORS: &'static str = "if_errors";
+    const IF_NOT_ERRORS: &'static str = "if_not_errors";
+}
+
+/// Configuration of `retry` macros (for example, `stop=attempts(3),wait=fixed(1)`).
+#[derive(Debug, PartialEq)]
+pub struct RetryingConfig {
+    pub stop: Option<StopConfig>,
+    pub wait: Option<WaitConfig>,
+    pub retry: Option<RetryConfig>,
+    pub envs_prefix: Option<String>,
+    pub envs_strict: bool,
+    pub policy: Option<String>,
+    pub clone_args: bool,
+    pub crate_path: Option<String>,
+    pub wrap_body: bool,
+    pub metrics_label: Option<String>,
+    pub cancel: Option<syn::Expr>,
+    pub abort: Option<syn::Path>,
+}
+
+impl RetryingConfig {
+    const STOP: &'static str = "stop";
+    const WAIT: &'static str = "wait";
+    const RETRY: &'static str = "retry";
+    const ENVS_PREFIX: &'static str = "envs_prefix";
+    const ENVS_STRICT: &'static str = "envs_strict";
+    const POLICY: &'static str = "policy";
+    const CLONE_ARGS: &'static str = "clone_args";
+    const CRATE: &'static str = "crate";
+    const WRAP_BODY: &'static str = "wrap_body";
+    const METRICS_LABEL: &'static str = "metrics_label";
+    const CANCEL: &'static str = "cancel";
+    const ABORT: &'static str = "abort";
+
+    const OPTIONS: [&'static str; 12] = [
+        Self::STOP,
+        Self::WAIT,
+        Self::RETRY,
+        Self::ENVS_PREFIX,
+        Self::ENVS_STRICT,
+        Self::POLICY,
+        Self::CLONE_ARGS,
+        Self::CRATE,
+        Self::WRAP_BODY,
+        Self::METRICS_LABEL,
+        Self::CANCEL,
+        Self::ABORT,
+    ];
+
+    fn new() -> RetryingConfig {
+        RetryingConfig {
+            stop: None,
+            wait: None,
+            retry: None,
+            envs_prefix: None,
+            envs_strict: false,
+            policy: None,
+            clone_args: false,
+            crate_path: None,
+            wrap_body: true,
+            metrics_label: None,
+            cancel: None,
+            abort: None,
+        }
+    }
+
+    fn stop(&mut self, expr: syn::Expr) -> Result<(), RetryConfigurationError> {
+        let parsed_config = Self::parse_stop_config(expr)?;
+
+        if parsed_config.attempts.is_some()
+            || parsed_config.duration.is_some()
+            || parsed_config.when.is_some()
+        {
+            self.stop = Some(parsed_config);
+        }
+        Ok(())
+    }
+
+    fn parse_stop_config(expr: syn::Expr) -> Result<StopConfig, RetryConfigurationError> {
+        let mut attempts = None;
+        let mut duration = None;
+        let mut when = None;
+        let functions = parse_functions_expr(expr)?;
+
+        for func in functions {
+            match func.ident.as_str() {
+                StopConfig::ATTEMPTS => attempts = Some(func.single_argument(RetryingConfig::STOP)?.parse::<u32>()?),
+                StopConfig::DURATION => duration = Some(func.single_argument(RetryingConfig::STOP)?.duration()?),
+                StopConfig::WHEN => when = Some(func.single_argument(RetryingConfig::STOP)?.path()?),
+                unknown => return Err(RetryConfigurationError::new_spanned(func.span,
+                    format!("Configuration {} is wrong for `{}`. Possible configuration option is {}.{}", unknown, RetryingConfig::STOP, StopConfig::OPTIONS.map(|o| format!("`{}`", o)).join(", "), did_you_mean(unknown, &StopConfig::OPTIONS))))
+            }
+        }
+        Ok(StopConfig {
+            attempts,
+            duration,
+            when,
+        })
+    }
+
+    fn wait(&mut self, expr: syn::Expr) -> Result<(), RetryConfigurationError> {
+        let parsed_config = Self::parse_wait_config(expr)?;
+        self.wait = Some(parsed_config);
+        Ok(())
+    }
+
+    fn parse_wait_config(expr: syn::Expr) -> Result<WaitConfig, RetryConfigurationError> {
+        let ParsedFunction { ident, args, span } = parse_function_call(expr)?;
+        match ident.as_str() {
+            WaitConfig::FIXED => {
+                if let Some(arg) = args.iter().find(|x| x.ident.is_some()).or_else(|| args.get(1)) {
+                    Err(RetryConfigurationError::new_spanned(arg.span, format!(
+                        "{}={} has only one argument without name.",
+                        RetryingConfig::WAIT,
+                        WaitConfig::FIXED
+                    )))
+                } else {
+                    let duration = args
+                        .first()
+                        .map(|x| x.duration())
+                        .transpose()?
+                        .unwrap_or(Duration::ZERO);
+                    Ok(WaitConfig::Fixed { duration })
+                }
+            }
+            WaitConfig::RANDOM => {
+                let mut min = Duration::ZERO;
+                let mut max = Duration::from_secs(3600);
+
+                for arg in args {
+                    match arg.ident.as_deref().unwrap_or_default() {
+                        WaitConfig::MIN => min = arg.duration()?,
+                        WaitConfig::MAX => max = arg.duration()?,
+                        unknown => return Err(RetryConfigurationError::new_spanned(arg.span, format!("{}={} has wrong configuration {}. Only `{}` and `{}` attributes is possible.{}", RetryingConfig::WAIT, WaitConfig::RANDOM, unknown, WaitConfig::MIN, WaitConfig::MAX, did_you_mean(unknown, &[WaitConfig::MIN, WaitConfig::MAX])))),
+                    }
+                }
+                check_min_max(WaitConfig::RANDOM, min, max, span)?;
+                Ok(WaitConfig::Random { min, max })
+            }
+            WaitConfig::EXPONENTIAL => {
+                let mut min = Duration::ZERO;
+                let mut max = Duration::from_secs(3600);
+                let mut multiplier = Duration::from_secs(1);
+                let mut exp_base: u32 = 2;
+
+                for arg in args {
+                    match arg.ident.as_deref().unwrap_or_default() {
+                        WaitConfig::MIN => min = arg.duration()?,
+                        WaitConfig::MAX => max = arg.duration()?,
+                        WaitConfig::MULTIPLIER => multiplier = arg.duration()?,
+                        WaitConfig::EXP_BASE => {
+                            exp_base = arg.parse::<u32>()?;
+                            if exp_base == 0 {
+                                return Err(RetryConfigurationError::new_spanned(arg.span, format!(
+                                    "{}={} `{}` should be positive",
+                                    RetryingConfig::WAIT,
+                                    WaitConfig::EXPONENTIAL,
+                                    WaitConfig::EXP_BASE
+                                )));
+                            }
+                        }
+                        unknown => return Err(RetryConfigurationError::new_spanned(arg.span, format!("{}={} has wrong configuration option `{}`. Only `{}`, `{}`, `{}` and `{}` attributes is possible.{}", RetryingConfig::WAIT, WaitConfig::EXPONENTIAL, unknown, WaitConfig::MIN, WaitConfig::MAX, WaitConfig::MULTIPLIER, WaitConfig::EXP_BASE, did_you_mean(unknown, &[WaitConfig::MIN, WaitConfig::MAX, WaitConfig::MULTIPLIER, WaitConfig::EXP_BASE])))),
+                    }
+                }
+
+                check_min_max(WaitConfig::EXPONENTIAL, min, max, span)?;
+                Ok(WaitConfig::Exponential {
+                    multiplier,
+                    min,
+                    max,
+                    exp_base,
+                })
+            }
+            unknown => Err(RetryConfigurationError::new_spanned(span, format!(
+                "Configuration {} is wrong for `{}`. Possible configuration is `{}`, `{}` and `{}`.{}",
+                unknown,
+                RetryingConfig::WAIT,
+                WaitConfig::FIXED,
+                WaitConfig::RANDOM,
+                WaitConfig::EXPONENTIAL,
+                did_you_mean(unknown, &[WaitConfig::FIXED, WaitConfig::RANDOM, WaitConfig::EXPONENTIAL])
+            ))),
+        }
+    }
+
+    fn retry(&mut self, expr: syn::Expr) -> Result<(), RetryConfigurationError> {
+        let parsed_config = Self::parse_retry_config(expr)?;
+        self.retry = Some(parsed_config);
+        Ok(())
+    }
+
+    fn parse_retry_config(expr: syn::Expr) -> Result<RetryConfig, RetryConfigurationError> {
+        let mut if_errors: Option<Vec<String>> = None;
+        let mut if_not_errors: Option<Vec<String>> = None;
+
+        let functions = parse_functions_expr(expr)?;
+
+        for func in functions {
+            let mut parsed_args = Vec::new();
+            for arg in &func.args {
+                match &arg.value {
+                    ParsedValue::ParsedPath(path) if arg.ident.is_none() => parsed_args.push(path.clone()),
+                    _ => return Err(RetryConfigurationError::new_spanned(arg.span, format!("`{}` arguments should be error types (for example `{}(std::num::ParseIntError)`)", RetryingConfig::RETRY, func.ident))),
+                }
+            }
+            let config = match func.ident.as_str() {
+                RetryConfig::IF_ERRORS => &mut if_errors,
+                RetryConfig::IF_NOT_ERRORS => &mut if_not_errors,
+                unknown => return Err(RetryConfigurationError::new_spanned(func.span, format!("Configuration {} is wrong for `{}`. Possible configuration option is `{}` and `{}`.{}", unknown, RetryingConfig::RETRY, RetryConfig::IF_ERRORS, RetryConfig::IF_NOT_ERRORS, did_you_mean(unknown, &[RetryConfig::IF_ERRORS, RetryConfig::IF_NOT_ERRORS]))))
+            };
+            if !parsed_args.is_empty() {
+                *config = Some(parsed_args);
+            }
+            if if_errors.is_some() && if_not_errors.is_some() {
+                return Err(RetryConfigurationError::new_spanned(func.span, format!("Configuration is wrong for `{}`. Only one of `{}` and `{}` should be configured at the same time", RetryingConfig::RETRY, RetryConfig::IF_ERRORS, RetryConfig::IF_NOT_ERRORS)));
+            }
+        }
+
+        Ok(RetryConfig {
+            if_errors,
+            if_not_errors,
+        })
+    }
+
+    fn envs_prefix(&mut self, expr: syn::Expr) -> Result<(), RetryConfigurationError> {
+        let parsed_config = Self::parse_envs_prefix_config(expr)?;
+
+        self.envs_prefix = Some(parsed_config);
+        Ok(())
+    }
+
+    fn parse_envs_prefix_config(expr: syn::Expr) -> Result<String, RetryConfigurationError> {
+        let span = expr.span();
+        match parse_value(expr) {
+            Ok(ParsedValue::ParsedString(v)) => Ok(v),
+            _ => Err(RetryConfigurationError::new_spanned(
+                span,
+                format!(
+                    "`{}` value should be string literal (for exampe `envs_prefix=\"retry\"`)",
+                    RetryingConfig::ENVS_PREFIX
+                ),
+            )),
+        }
+    }
+
+    fn envs_strict(&mut self, expr: syn::Expr) -> Result<(), RetryConfigurationError> {
+        self.envs_strict = Self::parse_bool_config(RetryingConfig::ENVS_STRICT, expr)?;
+        Ok(())
+    }
+
+    fn clone_args(&mut self, expr: syn::Expr) -> Result<(), RetryConfigurationError> {
+        self.clone_args = Self::parse_bool_config(RetryingConfig::CLONE_ARGS, expr)?;
+        Ok(())
+    }
+
+    fn wrap_body(&mut self, expr: syn::Expr) -> Result<(), RetryConfigurationError> {
+        self.wrap_body = Self::parse_bool_config(RetryingConfig::WRAP_BODY, expr)?;
+        Ok(())
+    }
+
+    fn metrics_label(&mut self, expr: syn::Expr) -> Result<(), RetryConfigurationError> {
+        let span = expr.span();
+        match parse_value(expr) {
+            Ok(ParsedValue::ParsedString(v)) => {
+                self.metrics_label = Some(v);
+                Ok(())
+            }
+            _ => Err(RetryConfigurationError::new_spanned(
+                span,
+                format!(
+                    "`{}` value should be string literal (for exampe `metrics_label=\"checkout\"`)",
+                    RetryingConfig::METRICS_LABEL
+                ),
+            )),
+        }
+    }
+
+    /// keeps future expression as is, it is evaluated by generated code at the beginning of function call.
+    fn cancel(&mut self, expr: syn::Expr) -> Result<(), RetryConfigurationError> {
+        match expr {
+            syn::Expr::Lit(_) => Err(RetryConfigurationError::new_spanned(
+                expr.span(),
+                format!(
+                    "`{}` value should be expression with cancellation future (for exampe `{}=token.cancelled()`)",
+                    RetryingConfig::CANCEL,
+                    RetryingConfig::CANCEL
+                ),
+            )),
+            expr => {
+                self.cancel = Some(expr);
+                Ok(())
+            }
+        }
+    }
+
+    fn abort(&mut self, expr: syn::Expr) -> Result<(), RetryConfigurationError> {
+        match expr {
+            syn::Expr::Path(syn::ExprPath { path, .. }) => {
+                self.abort = Some(path);
+                Ok(())
+            }
+            expr => Err(RetryConfigurationError::new_spanned(
+                expr.span(),
+                format!(
+                    "`{}` value should be path to `RetryAbort` handle (for exampe `{}=crate::SHUTDOWN`)",
+                    RetryingConfig::ABORT,
+                    RetryingConfig::ABORT
+                ),
+            )),
+        }
+    }
+
+    fn parse_bool_config(option: &str, expr: syn::Expr) -> Result<bool, RetryConfigurationError> {
+        let span = expr.span();
+        match parse_value(expr) {
+            Ok(ParsedValue::ParsedBool(v)) => Ok(v),
+            _ => Err(RetryConfigurationError::new_spanned(
+                span,
+                format!(
+                    "`{}` value should be bool literal (for exampe `{}=true`)",
+                    option, option
+                ),
+            )),
+        }
+    }
+
+    fn policy(&mut self, expr: syn::Expr) -> Result<(), RetryConfigurationError> {
+        let parsed_config = Self::parse_policy_config(expr)?;
+
+        self.policy = Some(parsed_config);
+        Ok(())
+    }
+
+    fn parse_policy_config(expr: syn::Expr) -> Result<String, RetryConfigurationError> {
+        let span = expr.span();
+        match parse_value(expr) {
+            Ok(ParsedValue::ParsedString(v)) if !v.is_empty() => Ok(v),
+            _ => Err(RetryConfigurationError::new_spanned(span, format!(
+                "`{}` value should be non-empty string literal with policy name (for exampe `policy=\"database\"`)",
+                RetryingConfig::POLICY
+            ))),
+        }
+    }
+
+    fn crate_path(&mut self, expr: syn::Expr) -> Result<(), RetryConfigurationError> {
+        let parsed_config = Self::parse_crate_config(expr)?;
+
+        self.crate_path = Some(parsed_config);
+        Ok(())
+    }
+
+    /// returns normalized path to `retrying` crate (for example, when dependency is renamed or re-exported by other crate).
+    fn parse_crate_config(expr: syn::Expr) -> Result<String, RetryConfigurationError> {
+        let span = expr.span();
+        match parse_value(expr) {
+            Ok(ParsedValue::ParsedString(v)) => match syn::parse_str::<syn::Path>(&v) {
+                Ok(path) => Ok(path.to_token_stream().to_string()),
+                Err(_) => Err(RetryConfigurationError::new_spanned(
+                    span,
+                    format!("`{}` value `{}` is not a valid path", RetryingConfig::CRATE, v),
+                )),
+            },
+            _ => Err(RetryConfigurationError::new_spanned(span, format!(
+                "`{}` value should be string literal with path to retrying crate (for exampe `crate=\"retry_lib\"`)",
+                RetryingConfig::CRATE
+            ))),
+        }
+    }
+
+    /// parses and validates comma-separated configuration options.
+    pub fn from_token_stream(args: TokenStream) -> Result<RetryingConfig, RetryConfigurationError> {
+        let mut config = RetryingConfig::new();
+        let mut envs_strict_span = None;
+
+        let args = AttributeArgs::parse_terminated.parse2(args).map_err(|e| {
+            RetryConfigurationError::new_spanned(
+                e.span(),
+                format!("Can't parse comma delimeted retry configuration. {}", e),
+            )
+        })?;
+
+        for arg in args {
+            match arg {
+                    syn::Meta::NameValue(name_value) => {
+                        let path = name_value.path;
+                        let ident = path
+                            .get_ident()
+                            .ok_or_else(|| {
+                                RetryConfigurationError::new_spanned(path.span(), String::from("Named value without ident"))
+                            })
+                            .map(|v| v.to_string().to_lowercase())?;
+                        let value = name_value.value;
+
+                        match ident.as_str()  {
+                            RetryingConfig::STOP => config.stop(value)?,
+                            RetryingConfig::WAIT => config.wait(value)?,
+                            RetryingConfig::RETRY => config.retry(value)?,
+                            RetryingConfig::ENVS_PREFIX => config.envs_prefix(value)?,
+                            RetryingConfig::ENVS_STRICT => {
+                                envs_strict_span = Some(path.span());
+                                config.envs_strict(value)?
+                            }
+                            RetryingConfig::POLICY => config.policy(value)?,
+                            RetryingConfig::CLONE_ARGS => config.clone_args(value)?,
+                            RetryingConfig::CRATE => config.crate_path(value)?,
+                            RetryingConfig::WRAP_BODY => config.wrap_body(value)?,
+                            RetryingConfig::METRICS_LABEL => config.metrics_label(value)?,
+                            RetryingConfig::CANCEL => config.cancel(value)?,
+                            RetryingConfig::ABORT => config.abort(value)?,
+                            unknown => return Err(RetryConfigurationError::new_spanned(path.span(), format!("Unknown configuration option `{}`. Possible values {}.{}", unknown, RetryingConfig::OPTIONS.map(|o| format!("`{}`", o)).join(", "), did_you_mean(unknown, &RetryingConfig::OPTIONS))))
+                        }
+                    }
+                    meta => {
+                        return Err(RetryConfigurationError::new_spanned(meta.span(), String::from(
+                            "Unknown format of configuration options. Only `name=value` is acceptable in retry config.",
+                        )))
+                    }
+                }
+        }
+
+        if config.envs_strict && config.envs_prefix.is_none() {
+            let error = RetryConfigurationError::new(format!(
+                "`{}` requires `{}` configuration option",
+                RetryingConfig::ENVS_STRICT,
+                RetryingConfig::ENVS_PREFIX
+            ));
+            return Err(envs_strict_span.map_or(error.clone(), |span| error.with_span(span)));
+        }
+        Ok(config)
+    }
+}
+
+/// Named policy defined by `retrying::policy!(NAME, <retry configuration>)` macros.
+pub struct PolicyDefinition {
+    pub vis: syn::Visibility,
+    pub ident: syn::Ident,
+    pub args: AttributeArgs,
+    pub config: RetryingConfig,
+}
+
+impl PolicyDefinition {
+    pub fn from_token_stream(
+        input: TokenStream,
+    ) -> Result<PolicyDefinition, RetryConfigurationError> {
+        let parser = |input: syn::parse::ParseStream| {
+            let vis: syn::Visibility = input.parse()?;
+            let ident: syn::Ident = input.parse()?;
+            if !input.is_empty() {
+                input.parse::<syn::Token![,]>()?;
+            }
+            let args: TokenStream = input.parse()?;
+            Ok((vis, ident, args))
+        };
+        let (vis, ident, args) = parser.parse2(input).map_err(|e| {
+            RetryConfigurationError::new_spanned(
+                e.span(),
+                format!(
+                    "Can't parse policy definition. Expected `policy!(NAME, <retry configuration>)`. {}",
+                    e
+                ),
+            )
+        })?;
+
+        // options are checked before parsing, so `policy=PATH` isn't reported as wrong registry name
+        if let Ok(args) = AttributeArgs::parse_terminated.parse2(args.clone()) {
+            for option in [
+                RetryingConfig::POLICY,
+                RetryingConfig::CANCEL,
+                RetryingConfig::ABORT,
+            ] {
+                if let Some(meta) = args.iter().find(|meta| meta.path().is_ident(option)) {
+                    return Err(RetryConfigurationError::new_spanned(
+                        meta.span(),
+                        format!(
+                            "`{}` configuration option can't be used in policy definition",
+                            option
+                        ),
+                    ));
+                }
+            }
+        }
+        let config = RetryingConfig::from_token_stream(args.clone())?;
+        let args = AttributeArgs::parse_terminated
+            .parse2(args)
+            .expect("policy configuration is already parsed");
+        // `PolicyDefinition::build()` of retrying can be called in runtime, but strategies are stored as constants
+        if config
+            .stop
+            .as_ref()
+            .map_or(false, |stop| stop.when.is_some())
+        {
+            let span = args
+                .iter()
+                .find(|meta| meta.path().is_ident(RetryingConfig::STOP))
+                .map_or_else(Span::call_site, |meta| meta.span());
+            return Err(RetryConfigurationError::new_spanned(
+                span,
+                format!(
+                    "`{}={}(..)` configuration option can't be used in policy definition",
+                    RetryingConfig::STOP,
+                    StopConfig::WHEN
+                ),
+            ));
+        }
+
+        Ok(PolicyDefinition {
+            vis,
+            ident,
+            args,
+            config,
+        })
+    }
+}
+
+/// splits configuration into path of policy defined by `retrying::policy!` macros (`policy=PATH`) and other options.
+/// Policy name in `retrying::PolicyRegistry` (`policy="name"`) is kept in other options.
+pub fn split_policy_path(
+    args: TokenStream,
+) -> Result<(Option<syn::Path>, TokenStream), RetryConfigurationError> {
+    let parsed = match AttributeArgs::parse_terminated.parse2(args.clone()) {
+        Ok(parsed) => parsed,
+        Err(_) => return Ok((None, args)),
+    };
+
+    let mut policy = None;
+    let mut other_args = AttributeArgs::new();
+    for meta in parsed {
+        match meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                path,
+                value: syn::Expr::Path(syn::ExprPath { path: value, .. }),
+                ..
+            }) if path.is_ident(RetryingConfig::POLICY) => {
+                // policy macros applies `retry` macros with the rest options, so the second path would be applied as well
+                if policy.is_some() {
+                    return Err(RetryConfigurationError::new_spanned(
+                        value.span(),
+                        format!(
+                            "`{}` configuration option can use only one policy defined by `retrying::policy!` macros",
+                            RetryingConfig::POLICY
+                        ),
+                    ));
+                }
+                policy = Some(value)
+            }
+            meta => other_args.push(meta),
+        }
+    }
+    Ok((policy, other_args.into_token_stream()))
+}
+
+fn check_min_max(
+    wait: &str,
+    min: Duration,
+    max: Duration,
+    span: Span,
+) -> Result<(), RetryConfigurationError> {
+    if min <= max {
+        Ok(())
+    } else {
+        Err(RetryConfigurationError::new_spanned(
+            span,
+            format!(
+                "{}={} `{}` ({:?}) should not be greater than `{}` ({:?})",
+                RetryingConfig::WAIT,
+                wait,
+                WaitConfig::MIN,
+                min,
+                WaitConfig::MAX,
+                max
+            ),
+        ))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum ParsedValue {
+    /// digits of integer literal, so values are parsed into target type without intermediate casts.
+    ParsedInt(String),
+    ParsedString(String),
+    ParsedBool(bool),
+    /// digits of float literal, so durations are parsed without `f32` rounding.
+    ParseFloat(String),
+    ParsedPath(String),
+}
+
+impl fmt::Display for ParsedValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParsedValue::ParsedInt(v) => write!(f, "{v}"),
+            ParsedValue::ParsedString(v) => write!(f, "{v}"),
+            ParsedValue::ParsedBool(v) => write!(f, "{v}"),
+            ParsedValue::ParseFloat(v) => write!(f, "{v}"),
+            ParsedValue::ParsedPath(v) => write!(f, "{v}"),
+        }
+    }
+}
+
+impl ParsedValue {
+    fn parse<T: FromStr>(&self) -> Result<T, RetryConfigurationError> {
+        self.to_string().parse::<T>().map_err(|_| {
+            RetryConfigurationError::new(format!(
+                "Failed to cast `{}` to `{}`",
+                self,
+                std::any::type_name::<T>()
+            ))
+        })
+    }
+
+    /// number literals are treated as seconds, string literals can contain units (for example `"500ms"` or `"1h30m"`).
+    fn duration(&self) -> Result<Duration, RetryConfigurationError> {
+        match self {
+            ParsedValue::ParsedInt(_) | ParsedValue::ParseFloat(_) | ParsedValue::ParsedString(_) => {
+                parse_duration(&self.to_string())
+            }
+            _ => Err(RetryConfigurationError::new(format!(
+                "Duration should be number of seconds or string with units (for example `\"500ms\"`), but got `{}`",
+                self
+            ))),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct FunctionArgument {
+    ident: Option<String>,
+    value: ParsedValue,
+    span: Span,
+}
+
+impl FunctionArgument {
+    fn parse<T: FromStr>(&self) -> Result<T, RetryConfigurationError> {
+        self.value.parse().map_err(|e| e.with_span(self.span))
+    }
+
+    fn duration(&self) -> Result<Duration, RetryConfigurationError> {
+        self.value.duration().map_err(|e| e.with_span(self.span))
+    }
+
+    /// returns path of function (for example `when(crate::is_shutdown)`).
+    fn path(&self) -> Result<String, RetryConfigurationError> {
+        match &self.value {
+            ParsedValue::ParsedPath(path) => Ok(path.clone()),
+            value => Err(RetryConfigurationError::new_spanned(
+                self.span,
+                format!("Expected path of function, but got `{}`", value),
+            )),
+        }
+    }
+}
+
+// spans can't be compared, so only parsed values are compared
+impl PartialEq for FunctionArgument {
+    fn eq(&self, other: &Self) -> bool {
+        self.ident == other.ident && self.value == other.value
+    }
+}
+
+#[derive(Debug)]
+struct ParsedFunction {
+    ident: String,
+    args: Vec<FunctionArgument>,
+    span: Span,
+}
+
+impl ParsedFunction {
+    /// returns the only argument without name (for example `attempts(3)`).
+    fn single_argument(&self, option: &str) -> Result<&FunctionArgument, RetryConfigurationError> {
+        match self.args.as_slice() {
+            [arg] if arg.ident.is_none() => Ok(arg),
+            _ => Err(RetryConfigurationError::new_spanned(
+                self.span,
+                format!(
+                    "{}={} has only one argument without name.",
+                    option, self.ident
+                ),
+            )),
+        }
+    }
+}
+
+impl PartialEq for ParsedFunction {
+    fn eq(&self, other: &Self) -> bool {
+        self.ident == other.ident && self.args == other.args
+    }
+}
+
+fn parse_functions_expr(
+    functions_expr: syn::Expr,
+) -> Result<Vec<ParsedFunction>, RetryConfigurationError> {
+    match functions_expr {
+        syn::Expr::Paren(syn::ExprParen { expr, .. }) => {
+            let deref_expr = *expr;
+            match deref_expr {
+                syn::Expr::Binary(syn::ExprBinary{left, right, op, ..}) => match op {
+                    syn::BinOp::BitOr(_) => {
+                        let mut parsed = parse_bit_or_functions(*left)?;
+                        parsed.push(parse_function_call(*right)?);
+                        Ok(parsed)
+                    },
+                    op => Err(RetryConfigurationError::new_spanned(op.span(), String::from("Incorrect symbol between configuration functions. Supported only bit or (`|`). For example, `function(args)|function2(args)")))
+                },
+                syn::Expr::Call(_) => {
+                    let parsed_function = parse_function_call(deref_expr)?;
+                    Ok(vec![parsed_function])
+                }
+                expr => Err(RetryConfigurationError::new_spanned(expr.span(), String::from("Incorrect expression between paren. Supported only one function `function(args)` or multiple functions `function(args)|function2(args)")))
+            }
+        }
+        _ => {
+            let parsed_function = parse_function_call(functions_expr)?;
+            Ok(vec![parsed_function])
+        }
+    }
+}
+
+/// parses left side of `function(args)|function2(args)|function3(args)` chain.
+fn parse_bit_or_functions(expr: syn::Expr) -> Result<Vec<ParsedFunction>, RetryConfigurationError> {
+    match expr {
+        syn::Expr::Binary(syn::ExprBinary {
+            left,
+            right,
+            op: syn::BinOp::BitOr(_),
+            ..
+        }) => {
+            let mut parsed = parse_bit_or_functions(*left)?;
+            parsed.push(parse_function_call(*right)?);
+            Ok(parsed)
+        }
+        expr => Ok(vec![parse_function_call(expr)?]),
+    }
+}
+
+fn parse_function_call(
+    function_expr: syn::Expr,
+) -> Result<ParsedFunction, RetryConfigurationError> {
+    match function_expr {
+        syn::Expr::Call(syn::ExprCall { func, args, .. }) => {
+            let span = func.span();
+            let ident = parse_ident(*func)?;
+            let args: Vec<FunctionArgument> = parse_function_arguments(args)?;
+            Ok(ParsedFunction { ident, args, span })
+        }
+        expr => Err(RetryConfigurationError::new_spanned(
+            expr.span(),
+            String::from("Not a function. Configuration should look like `function(args)`"),
+        )),
+    }
+}
+
+fn parse_function_arguments(
+    arguments: Punctuated<syn::Expr, syn::Token![,]>,
+) -> Result<Vec<FunctionArgument>, RetryConfigurationError> {
+    let mut parsed_arguments: Vec<FunctionArgument> = Vec::new();
+
+    for arg in arguments {
+        let span = arg.span();
+        match arg {
+            syn::Expr::Assign(syn::ExprAssign { left, right, .. }) => {
+                let name = parse_ident(*left)?;
+                let parsed_value = parse_value(*right)?;
+                parsed_arguments.push(FunctionArgument {
+                    ident: Some(name),
+                    value: parsed_value,
+                    span,
+                });
+            }
+            expr => {
+                let parsed_value = parse_value(expr)?;
+                parsed_arguments.push(FunctionArgument {
+                    ident: None,
+                    value: parsed_value,
+                    span,
+                });
+            }
+        }
+    }
+    Ok(parsed_arguments)
+}
+
+fn parse_value(expr: syn::Expr) -> Result<ParsedValue, RetryConfigurationError> {
+    match expr {
+        syn::Expr::Path(syn::ExprPath { path, .. }) => {
+            Ok(ParsedValue::ParsedPath(quote!(#path).to_string()))
+        }
+        syn::Expr::Lit(syn::ExprLit { lit, .. }) => match lit {
+            syn::Lit::Int(lit) => Ok(ParsedValue::ParsedInt(lit.base10_digits().to_string())),
+            syn::Lit::Str(s) => Ok(ParsedValue::ParsedString(s.value())),
+            syn::Lit::Verbatim(s) => Ok(ParsedValue::ParsedString(s.to_string())),
+            syn::Lit::Bool(b) => Ok(ParsedValue::ParsedBool(b.value)),
+            syn::Lit::Float(lit) => Ok(ParsedValue::ParseFloat(lit.base10_digits().to_string())),
+            lit => Err(RetryConfigurationError::new_spanned(lit.span(), String::from(
+                "Unsupported literal. Currently supported only Int, Str, Verbatim, Bool and Float",
+            ))),
+        },
+        expr => Err(RetryConfigurationError::new_spanned(expr.span(), String::from(
+            "Unsupported value. Currently supported only Path, Int, Str, Verbatim, Bool and Float",
+        ))),
+    }
+}
+
+fn parse_ident(expr: syn::Expr) -> Result<String, RetryConfigurationError> {
+    match expr {
+        syn::Expr::Path(syn::ExprPath { path, .. }) => path
+            .get_ident()
+            .ok_or_else(|| {
+                RetryConfigurationError::new_spanned(
+                    path.span(),
+                    String::from("Named value without ident"),
+                )
+            })
+            .map(|v| v.to_string()),
+        expr => Err(RetryConfigurationError::new_spanned(
+            expr.span(),
+            String::from("Incorrect expression. Expected identifier"),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::*;
+    use quote::quote;
+    use std::vec;
+
+    #[test]
+    fn test_parse_stop_config() {
+        let mut config = RetryingConfig::new();
+        config.stop(syn::parse_quote!(attempts(5))).unwrap();
+
+        assert_eq!(
+            config.stop,
+            Some(StopConfig {
+                attempts: Some(5),
+                duration: None,
+                when: None
+            })
+        );
+
+        config.stop(syn::parse_quote!(duration(0.5))).unwrap();
+        assert_eq!(
+            config.stop,
+            Some(StopConfig {
+                attempts: None,
+                duration: Some(Duration::from_millis(500)),
+                when: None
+            })
+        );
+
+        config
+            .stop(syn::parse_quote!(duration(1234567.89)))
+            .unwrap();
+        assert_eq!(
+            config.stop,
+            Some(StopConfig {
+                attempts: None,
+                duration: Some(Duration::new(1234567, 890_000_000)),
+                when: None
+            })
+        );
+
+        config
+            .stop(syn::parse_quote!(duration(5000000000)))
+            .unwrap();
+        assert_eq!(
+            config.stop.as_ref().and_then(|stop| stop.duration),
+            Some(Duration::from_secs(5_000_000_000))
+        );
+
+        config.stop(syn::parse_quote!(duration("1h30m"))).unwrap();
+        assert_eq!(
+            config.stop,
+            Some(StopConfig {
+                attempts: None,
+                duration: Some(Duration::from_secs(5400)),
+                when: None
+            })
+        );
+
+        config
+            .stop(syn::parse_quote!((attempts(5) | duration(0.5))))
+            .unwrap();
+        assert_eq!(
+            config.stop,
+            Some(StopConfig {
+                attempts: Some(5),
+                duration: Some(Duration::from_millis(500)),
+                when: None
+            })
+        );
+
+        assert!(config
+            .stop(syn::parse_quote!(duration("5 parsecs")))
+            .is_err());
+        assert!(config.stop(syn::parse_quote!(attempts(0.5))).is_err());
+
+        config
+            .stop(syn::parse_quote!(
+                (attempts(5) | duration(0.5) | when(crate::is_shutdown))
+            ))
+            .unwrap();
+        assert_eq!(
+            config.stop,
+            Some(StopConfig {
+                attempts: Some(5),
+                duration: Some(Duration::from_millis(500)),
+                when: Some(String::from("crate :: is_shutdown"))
+            })
+        );
+
+        config.stop(syn::parse_quote!(when(is_shutdown))).unwrap();
+        assert_eq!(
+            config.stop,
+            Some(StopConfig {
+                attempts: None,
+                duration: None,
+                when: Some(String::from("is_shutdown"))
+            })
+        );
+
+        assert!(config.stop(syn::parse_quote!(when("is_shutdown"))).is_err());
+        assert!(config
+            .stop(syn::parse_quote!((attempts(5) & when(is_shutdown))))
+            .is_err());
+    }
+
+    #[test]
+    fn test_parse_wait_config() {
+        let mut config = RetryingConfig::new();
+
+        config.wait(syn::parse_quote!(fixed(4.4))).unwrap();
+        assert_eq!(
+            config.wait,
+            Some(WaitConfig::Fixed {
+                duration: Duration::from_millis(4400)
+            })
+        );
+
+        config.wait(syn::parse_quote!(fixed("500ms"))).unwrap();
+        assert_eq!(
+            config.wait,
+            Some(WaitConfig::Fixed {
+                duration: Duration::from_millis(500)
+            })
+        );
+
+        config
+            .wait(syn::parse_quote!(random(min = 0.4, max = "1.5s")))
+            .unwrap();
+        assert_eq!(
+            config.wait,
+            Some(WaitConfig::Random {
+                min: Duration::from_millis(400),
+                max: Duration::from_millis(1500)
+            })
+        );
+
+        config
+            .wait(syn::parse_quote!(exponential(
+                min = 0.4,
+                max = 1.5,
+                multiplier = 1.2,
+                exp_base = 2
+            )))
+            .unwrap();
+        assert_eq!(
+            config.wait,
+            Some(WaitConfig::Exponential {
+                multiplier: Duration::from_millis(1200),
+                min: Duration::from_millis(400),
+                max: Duration::from_millis(1500),
+                exp_base: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_wait_config_out_of_range() {
+        let mut config = RetryingConfig::new();
+
+        assert!(config
+            .wait(syn::parse_quote!(random(min = 2, max = 1.5)))
+            .is_err());
+        assert!(config
+            .wait(syn::parse_quote!(exponential(min = 2, max = 1.5)))
+            .is_err());
+        assert!(config
+            .wait(syn::parse_quote!(random(min = "1m", max = "30s")))
+            .is_err());
+        assert!(config
+            .wait(syn::parse_quote!(exponential(exp_base = 0)))
+            .is_err());
+    }
+
+    #[test]
+    fn test_parse_retry_config() {
+        let mut config = RetryingConfig::new();
+
+        config
+            .retry(syn::parse_quote!(if_errors(
+                syn::Err,
+                ::std::num::ParseIntError
+            )))
+            .unwrap();
+        assert_eq!(
+            config.retry,
+            Some(RetryConfig {
+                if_errors: Some(vec![
+                    "syn :: Err".to_string(),
+                    ":: std :: num :: ParseIntError".to_string()
+                ]),
+                if_not_errors: None
+            })
+        );
+
+        config
+            .retry(syn::parse_quote!(if_not_errors(
+                syn::Err,
+                ::std::num::ParseIntError
+            )))
+            .unwrap();
+        assert_eq!(
+            config.retry,
+            Some(RetryConfig {
+                if_errors: None,
+                if_not_errors: Some(vec![
+                    "syn :: Err".to_string(),
+                    ":: std :: num :: ParseIntError".to_string()
+                ])
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_envs_prefix_config() {
+        let mut config = RetryingConfig::new();
+
+        config.envs_prefix(syn::parse_quote!("TEST")).unwrap();
+        assert_eq!(config.envs_prefix, Some("TEST".to_string()));
+    }
+
+    #[test]
+    fn test_parse_envs_strict_config() {
+        let mut config = RetryingConfig::new();
+
+        config.envs_strict(syn::parse_quote!(true)).unwrap();
+        assert!(config.envs_strict);
+
+        assert!(config.envs_strict(syn::parse_quote!("true")).is_err());
+        assert!(RetryingConfig::from_token_stream(quote!(envs_strict = true)).is_err());
+    }
+
+    #[test]
+    fn test_parse_clone_args_config() {
+        let mut config = RetryingConfig::new();
+
+        config.clone_args(syn::parse_quote!(true)).unwrap();
+        assert!(config.clone_args);
+
+        assert!(config.clone_args(syn::parse_quote!(1)).is_err());
+    }
+
+    #[test]
+    fn test_parse_wrap_body_config() {
+        let mut config = RetryingConfig::new();
+        assert!(config.wrap_body);
+
+        config.wrap_body(syn::parse_quote!(false)).unwrap();
+        assert!(!config.wrap_body);
+
+        assert!(config.wrap_body(syn::parse_quote!("false")).is_err());
+    }
+
+    #[test]
+    fn test_parse_policy_config() {
+        let mut config = RetryingConfig::new();
+
+        config.policy(syn::parse_quote!("database")).unwrap();
+        assert_eq!(config.policy, Some("database".to_string()));
+
+        assert!(config.policy(syn::parse_quote!("")).is_err());
+        assert!(config.policy(syn::parse_quote!(5)).is_err());
+        assert!(config.policy(syn::parse_quote!(db::DB_POLICY)).is_err());
+    }
+
+    #[test]
+    fn test_parse_metrics_label_config() {
+        let mut config = RetryingConfig::new();
+
+        config.metrics_label(syn::parse_quote!("checkout")).unwrap();
+        assert_eq!(config.metrics_label, Some("checkout".to_string()));
+
+        assert!(config.metrics_label(syn::parse_quote!(checkout)).is_err());
+    }
+
+    #[test]
+    fn test_parse_cancel_config() {
+        let mut config = RetryingConfig::new();
+
+        config.cancel(syn::parse_quote!(token.cancelled())).unwrap();
+        assert_eq!(config.cancel, Some(syn::parse_quote!(token.cancelled())));
+
+        assert!(config.cancel(syn::parse_quote!("token")).is_err());
+    }
+
+    #[test]
+    fn test_parse_abort_config() {
+        let mut config = RetryingConfig::new();
+
+        config.abort(syn::parse_quote!(crate::SHUTDOWN)).unwrap();
+        assert_eq!(config.abort, Some(syn::parse_quote!(crate::SHUTDOWN)));
+
+        assert!(config.abort(syn::parse_quote!("SHUTDOWN")).is_err());
+        assert!(config.abort(syn::parse_quote!(shutdown())).is_err());
+    }
+
+    #[test]
+    fn test_parse_crate_config() {
+        let mut config = RetryingConfig::new();
+
+        config.crate_path(syn::parse_quote!("retry_lib")).unwrap();
+        assert_eq!(config.crate_path, Some("retry_lib".to_string()));
+
+        config
+            .crate_path(syn::parse_quote!("::facade::retrying"))
+            .unwrap();
+        assert_eq!(config.crate_path, Some(":: facade :: retrying".to_string()));
+
+        assert!(config.crate_path(syn::parse_quote!("retry lib")).is_err());
+        assert!(config.crate_path(syn::parse_quote!(retry_lib)).is_err());
+    }
+
+    #[test]
+    fn test_policy_definition_from_token_stream() {
+        let definition = PolicyDefinition::from_token_stream(quote!(
+            pub(crate) DB_POLICY,
+            stop = attempts(3),
+            retry = if_errors(crate::Error)
+        ))
+        .unwrap();
+
+        assert_eq!(definition.ident.to_string(), "DB_POLICY");
+        assert_eq!(definition.vis.to_token_stream().to_string(), "pub (crate)");
+        assert_eq!(definition.args.len(), 2);
+        assert_eq!(
+            definition.config.stop,
+            Some(StopConfig {
+                attempts: Some(3),
+                duration: None,
+                when: None
+            })
+        );
+
+        let definition = PolicyDefinition::from_token_stream(quote!(EMPTY)).unwrap();
+        assert!(definition.args.is_empty());
+        assert_eq!(definition.config, RetryingConfig::new());
+
+        assert!(PolicyDefinition::from_token_stream(quote!("DB_POLICY")).is_err());
+        assert!(PolicyDefinition::from_token_stream(quote!(DB_POLICY, stop = wrong(1))).is_err());
+        assert!(PolicyDefinition::from_token_stream(quote!(DB_POLICY, policy = "db")).is_err());
+        assert!(PolicyDefinition::from_token_stream(quote!(DB_POLICY, policy = OTHER)).is_err());
+        assert!(PolicyDefinition::from_token_stream(quote!(DB_POLICY, cancel = shutdown)).is_err());
+        assert!(PolicyDefinition::from_token_stream(quote!(DB_POLICY, abort = SHUTDOWN)).is_err());
+        assert!(
+            PolicyDefinition::from_token_stream(quote!(DB_POLICY, stop = when(is_shutdown)))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_split_policy_path() {
+        let (policy, args) =
+            split_policy_path(quote!(stop = attempts(2), policy = db::DB_POLICY)).unwrap();
+        assert_eq!(
+            policy.unwrap().to_token_stream().to_string(),
+            "db :: DB_POLICY"
+        );
+        assert_eq!(args.to_string(), "stop = attempts (2)");
+
+        let (policy, args) = split_policy_path(quote!(policy = "database")).unwrap();
+        assert!(policy.is_none());
+        assert_eq!(args.to_string(), "policy = \"database\"");
+
+        let (policy, args) =
+            split_policy_path(quote!(policy = "database", policy = DB_POLICY)).unwrap();
+        assert_eq!(policy.unwrap().to_token_stream().to_string(), "DB_POLICY");
+        assert_eq!(args.to_string(), "policy = \"database\"");
+
+        assert!(split_policy_path(quote!(policy = DB_POLICY, policy = OTHER)).is_err());
+    }
+
+    #[test]
+    fn test_from_token_stream() {
+        let token_stream = quote!(
+            stop = (attempts(1) | duration(5.5)),
+            wait = fixed(0.5),
+            retry = if_errors(::syn::Error, ::std::num::ParseIntError),
+            envs_prefix = "TEST",
+            envs_strict = true,
+            policy = "database",
+            clone_args = true,
+            crate = "retry_lib",
+            wrap_body = false,
+            metrics_label = "checkout",
+            cancel = token.cancelled(),
+            abort = crate::SHUTDOWN
+        );
+
+        let expected = RetryingConfig {
+            stop: Some(StopConfig {
+                attempts: Some(1),
+                duration: Some(Duration::from_millis(5500)),
+                when: None,
+            }),
+            wait: Some(WaitConfig::Fixed {
+                duration: Duration::from_millis(500),
+            }),
+            retry: Some(RetryConfig {
+                if_errors: Some(vec![
+                    ":: syn :: Error".to_string(),
+                    ":: std :: num :: ParseIntError".to_string(),
+                ]),
+                if_not_errors: None,
+            }),
+            envs_prefix: Some(String::from("TEST")),
+            envs_strict: true,
+            policy: Some(String::from("database")),
+            clone_args: true,
+            crate_path: Some(String::from("retry_lib")),
+            wrap_body: false,
+            metrics_label: Some(String::from("checkout")),
+            cancel: Some(syn::parse_quote!(token.cancelled())),
+            abort: Some(syn::parse_quote!(crate::SHUTDOWN)),
+        };
+
+        let result = RetryingConfig::from_token_stream(token_stream).unwrap();
+
+        assert_eq!(result, expected);
+
+        let result = RetryingConfig::from_token_stream(quote!()).unwrap();
+        assert_eq!(result, RetryingConfig::new());
+    }
+
+    #[test]
+    fn test_parse_functions_expr() {
+        let expected = vec![
+            ParsedFunction {
+                ident: String::from("function1"),
+                args: vec![FunctionArgument {
+                    ident: None,
+                    value: ParsedValue::ParsedInt(String::from("1")),
+                    span: Span::call_site(),
+                }],
+                span: Span::call_site(),
+            },
+            ParsedFunction {
+                ident: String::from("function2"),
+                args: vec![FunctionArgument {
+                    ident: Some(String::from("test")),
+                    value: ParsedValue::ParseFloat(String::from("5.5")),
+                    span: Span::call_site(),
+                }],
+                span: Span::call_site(),
+            },
+        ];
+        let result =
+            parse_functions_expr(syn::parse_quote!((function1(1) | function2(test = 5.5))))
+                .unwrap();
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_parse_function_call() {
+        let expected = ParsedFunction {
+            ident: String::from("function1"),
+            args: vec![
+                FunctionArgument {
+                    ident: None,
+                    value: ParsedValue::ParsedInt(String::from("1")),
+                    span: Span::call_site(),
+                },
+                FunctionArgument {
+                    ident: Some(String::from("test")),
+                    value: ParsedValue::ParseFloat(String::from("2.4")),
+                    span: Span::call_site(),
+                },
+            ],
+            span: Span::call_site(),
+        };
+        let result = parse_function_call(syn::parse_quote!(function1(1, test = 2.4))).unwrap();
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_parse_function_arguments() {
+        let expected = vec![FunctionArgument {
+            ident: Some("x".to_string()),
+            value: ParsedValue::ParsedInt(String::from("1")),
+            span: Span::call_site(),
+        }];
+        let result = parse_function_arguments(syn::parse_quote!(x = 1)).unwrap();
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_parse_value() {
+        let expected = ParsedValue::ParsedPath("std :: mem :: replace".to_string());
+        let result = parse_value(syn::parse_quote!(std::mem::replace)).unwrap();
+        assert_eq!(expected, result);
+
+        let expected = ParsedValue::ParsedInt(String::from("1"));
+        let result = parse_value(syn::parse_quote!(1)).unwrap();
+        assert_eq!(expected, result);
+
+        let expected = ParsedValue::ParseFloat(String::from("1.5"));
+        let result = parse_value(syn::parse_quote!(1.5)).unwrap();
+        assert_eq!(expected, result);
+
+        let expected = ParsedValue::ParsedBool(false);
+        let result = parse_value(syn::parse_quote!(false)).unwrap();
+        assert_eq!(expected, result);
+
+        let expected = ParsedValue::ParsedString("test".to_string());
+        let result = parse_value(syn::parse_quote!("test")).unwrap();
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_parse_incorrect_value() {
+        parse_value(syn::parse_quote!(let sd = s)).unwrap();
+    }
+}
@@ -0,0 +1,156 @@
+use crate::errors::RetryConfigurationError;
+use std::time::Duration;
+
+/// parses duration from number of seconds (`1.5`) or from human-readable string with units (`500ms`, `2m`, `1h30m`).
+/// Supported units are `ns`, `us` (`µs`), `ms`, `s`, `m` (`min`), `h` and `d`.
+pub fn parse_duration(value: &str) -> Result<Duration, RetryConfigurationError> {
+    let value = value.trim();
+    let error = |reason: &str| {
+        RetryConfigurationError::new(format!(
+            "Failed to parse duration '{}'. {}. Expected number of seconds or value with units like `500ms`, `2m`, `1h30m`",
+            value, reason
+        ))
+    };
+
+    if is_decimal(value) {
+        return decimal_to_duration(value, NANOS_PER_SECOND)
+            .ok_or_else(|| error("Value is out of range"));
+    }
+    if let Ok(seconds) = value.parse::<f64>() {
+        return seconds_to_duration(seconds).ok_or_else(|| error("Value is out of range"));
+    }
+    if value.is_empty() {
+        return Err(error("Value is empty"));
+    }
+
+    let mut total = Duration::ZERO;
+    let mut rest = value;
+    while !rest.is_empty() {
+        let number_end = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let (number, tail) = rest.split_at(number_end);
+        let tail = tail.trim_start();
+        let unit_end = tail
+            .find(|c: char| c.is_ascii_digit() || c == '.' || c.is_whitespace())
+            .unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(unit_end);
+
+        if !is_decimal(number) {
+            return Err(error(&format!("Wrong number '{}'", number)));
+        }
+        let unit_nanos = match unit {
+            "ns" => 1,
+            "us" | "µs" => 1_000,
+            "ms" => 1_000_000,
+            "s" => NANOS_PER_SECOND,
+            "m" | "min" => 60 * NANOS_PER_SECOND,
+            "h" => 3600 * NANOS_PER_SECOND,
+            "d" => 86400 * NANOS_PER_SECOND,
+            "" => return Err(error("Unit is missing")),
+            unknown => return Err(error(&format!("Unknown unit '{}'", unknown))),
+        };
+
+        total = decimal_to_duration(number, unit_nanos)
+            .and_then(|d| total.checked_add(d))
+            .ok_or_else(|| error("Value is out of range"))?;
+        rest = tail.trim_start();
+    }
+    Ok(total)
+}
+
+const NANOS_PER_SECOND: u128 = 1_000_000_000;
+
+/// checks that `value` is a number without sign and exponent (for example `1`, `1.5` or `.5`).
+fn is_decimal(value: &str) -> bool {
+    let mut parts = value.splitn(2, '.');
+    let integer = parts.next().unwrap_or_default();
+    let fraction = parts.next().unwrap_or_default();
+    !(integer.is_empty() && fraction.is_empty())
+        && integer
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit())
+}
+
+/// converts decimal number of units with `unit_nanos` nanoseconds into duration without floating point rounding.
+/// Fraction digits beyond nanoseconds precision are truncated.
+fn decimal_to_duration(value: &str, unit_nanos: u128) -> Option<Duration> {
+    let (integer, fraction) = value.split_once('.').unwrap_or((value, ""));
+    // 20 digits keep `fraction * unit_nanos` within `u128` for all units
+    let fraction = &fraction[..fraction.len().min(20)];
+    let integer = match integer {
+        "" => 0,
+        integer => integer.parse::<u128>().ok()?,
+    };
+    let fraction_nanos = match fraction {
+        "" => 0,
+        fraction => fraction.parse::<u128>().ok()? * unit_nanos / 10u128.pow(fraction.len() as u32),
+    };
+    let nanos = integer
+        .checked_mul(unit_nanos)?
+        .checked_add(fraction_nanos)?;
+    let seconds = u64::try_from(nanos / NANOS_PER_SECOND).ok()?;
+    Some(Duration::new(seconds, (nanos % NANOS_PER_SECOND) as u32))
+}
+
+fn seconds_to_duration(seconds: f64) -> Option<Duration> {
+    if seconds.is_finite() && seconds >= 0.0 && seconds < u64::MAX as f64 {
+        Some(Duration::from_secs_f64(seconds))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("1.5").unwrap(), Duration::from_millis(1500));
+        assert_eq!(parse_duration(" 3 ").unwrap(), Duration::from_secs(3));
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration("2m").unwrap(), Duration::from_secs(120));
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(
+            parse_duration("1h 30m 5s").unwrap(),
+            Duration::from_secs(5405)
+        );
+        assert_eq!(parse_duration("1.5s").unwrap(), Duration::from_millis(1500));
+        assert_eq!(parse_duration("10us").unwrap(), Duration::from_micros(10));
+        assert_eq!(parse_duration("1d").unwrap(), Duration::from_secs(86400));
+
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("-1").is_err());
+        assert!(parse_duration("10 parsecs").is_err());
+        assert_eq!(parse_duration("10 ms").unwrap(), Duration::from_millis(10));
+        assert!(parse_duration("ms").is_err());
+        assert!(parse_duration("1.2.3s").is_err());
+    }
+
+    #[test]
+    fn test_parse_duration_precision() {
+        assert_eq!(
+            parse_duration("1234567.89").unwrap(),
+            Duration::new(1234567, 890_000_000)
+        );
+        assert_eq!(
+            parse_duration("0.000000001").unwrap(),
+            Duration::from_nanos(1)
+        );
+        assert_eq!(parse_duration("1.0000000019").unwrap(), Duration::new(1, 1));
+        assert_eq!(parse_duration(".5").unwrap(), Duration::from_millis(500));
+        assert_eq!(
+            parse_duration("1.1ms").unwrap(),
+            Duration::from_micros(1100)
+        );
+        assert_eq!(
+            parse_duration("1.000001h").unwrap(),
+            Duration::new(3600, 3_600_000)
+        );
+        assert_eq!(parse_duration("1e3").unwrap(), Duration::from_secs(1000));
+        assert!(parse_duration(".").is_err());
+        assert!(parse_duration("99999999999999999999999").is_err());
+    }
+}
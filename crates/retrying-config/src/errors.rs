@@ -0,0 +1,99 @@
+use proc_macro2::{Span, TokenStream};
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub struct RetryConfigurationError {
+    msg: String,
+    span: Span,
+}
+
+impl RetryConfigurationError {
+    pub fn new(msg: String) -> RetryConfigurationError {
+        RetryConfigurationError {
+            msg,
+            span: Span::call_site(),
+        }
+    }
+
+    pub fn new_spanned(span: Span, msg: String) -> RetryConfigurationError {
+        RetryConfigurationError { msg, span }
+    }
+
+    /// points error to the offending token.
+    pub fn with_span(self, span: Span) -> RetryConfigurationError {
+        RetryConfigurationError { span, ..self }
+    }
+
+    /// returns error message without the prefix about `retry` macros.
+    pub fn message(&self) -> &str {
+        &self.msg
+    }
+
+    /// returns `compile_error!` invocation that points to the offending token.
+    pub fn to_compile_error(&self) -> TokenStream {
+        syn::Error::new(self.span, self).to_compile_error()
+    }
+}
+
+impl fmt::Display for RetryConfigurationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Retrying macros `retry` has incorrect configuration. Error: {}",
+            self.msg
+        )
+    }
+}
+
+/// returns hint like ` Did you mean `stop`?` for misspelled `unknown` value or empty string if there is no similar candidate.
+pub(crate) fn did_you_mean(unknown: &str, candidates: &[&str]) -> String {
+    let unknown = unknown.to_lowercase();
+    candidates
+        .iter()
+        .map(|candidate| (levenshtein(&unknown, candidate), candidate))
+        .filter(|(distance, candidate)| *distance <= (candidate.len() / 3).max(2))
+        .min_by_key(|(distance, _)| *distance)
+        .map_or(String::new(), |(_, candidate)| {
+            format!(" Did you mean `{}`?", candidate)
+        })
+}
+
+fn levenshtein(left: &str, right: &str) -> usize {
+    let right: Vec<char> = right.chars().collect();
+    let mut previous: Vec<usize> = (0..=right.len()).collect();
+
+    for (i, l) in left.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, r) in right.iter().enumerate() {
+            let substitution = previous[j] + usize::from(l != *r);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[right.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_did_you_mean() {
+        let candidates = ["stop", "wait", "retry", "envs_prefix"];
+
+        assert_eq!(did_you_mean("stpo", &candidates), " Did you mean `stop`?");
+        assert_eq!(did_you_mean("WAIT", &candidates), " Did you mean `wait`?");
+        assert_eq!(
+            did_you_mean("env_prefix", &candidates),
+            " Did you mean `envs_prefix`?"
+        );
+        assert_eq!(did_you_mean("something", &candidates), "");
+    }
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("retry", "retry"), 0);
+    }
+}
@@ -0,0 +1,19 @@
+//! Parser of configuration syntax of `retrying::retry` macros.
+//!
+//! ```
+//! use retrying_config::RetryingConfig;
+//!
+//! let config = RetryingConfig::from_token_stream("stop=attempts(3),wait=fixed(1)".parse().unwrap()).unwrap();
+//! assert_eq!(config.stop.unwrap().attempts, Some(3));
+//! ```
+
+mod config;
+mod duration;
+mod errors;
+
+pub use config::{
+    split_policy_path, AttributeArgs, PolicyDefinition, RetryConfig, RetryingConfig, StopConfig,
+    WaitConfig,
+};
+pub use duration::parse_duration;
+pub use errors::RetryConfigurationError;
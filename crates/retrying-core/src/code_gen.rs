@@ -1,182 +1,773 @@
-use crate::config::*;
-use proc_macro2::TokenStream;
-use quote::quote;
-use syn::{ItemFn, Signature};
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::{quote, quote_spanned, ToTokens};
+use retrying_config::*;
+use std::time::Duration;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{FnArg, ItemFn, ReturnType, Token};
+
+/// Kind of function return type. `retry` configuration option (errors filter) is available only for `Result`.
+#[derive(Debug, PartialEq)]
+enum ReturnKind {
+    Result,
+    Option,
+}
 
 pub(crate) fn add_retry_code_into_function(
     function: ItemFn,
     config: RetryingConfig,
-) -> TokenStream {
+) -> Result<TokenStream, RetryConfigurationError> {
     let ItemFn {
         attrs,
         vis,
-        sig,
+        mut sig,
         block,
-        ..
     } = function;
 
-    let Signature {
-        constness,
-        asyncness,
-        unsafety,
-        abi,
-        fn_token,
-        ident,
-        generics:
-            syn::Generics {
-                params: gen_params,
-                where_clause,
-                ..
-            },
-        paren_token: _,
-        inputs: params,
-        variadic,
-        output: return_type,
-        ..
-    } = sig;
-
     let RetryingConfig {
         stop,
         wait,
         retry,
         envs_prefix,
-        ..
+        envs_strict,
+        policy,
+        clone_args,
+        crate_path,
+        wrap_body,
+        metrics_label,
+        cancel,
+        abort,
     } = config;
 
-    let let_retrying_stop = stop.map_or(quote!(), |s| {
-        let stop = prepare_stop(s, envs_prefix.clone());
-        quote!(
-            use ::retrying::stop::Stop;
-            let retrying_stop = #stop;
-        )
+    let boxed_future = boxed_future(&sig.output, &block)?;
+    let (result_type, block) = match &boxed_future {
+        Some((_, output, async_block)) => (*output, *async_block),
+        None => (result_type(&sig.output, sig.ident.span())?, block.as_ref()),
+    };
+    let return_kind = return_kind(result_type)?;
+    let is_async = sig.asyncness.is_some() || boxed_future.is_some();
+
+    let (params, let_cloned_args) = prepare_args(std::mem::take(&mut sig.inputs), clone_args)?;
+    sig.inputs = params;
+    let attempt = if let_cloned_args.is_empty() {
+        quote!(#block)
+    } else {
+        quote!({
+            #let_cloned_args
+            #block
+        })
+    };
+
+    if let (ReturnKind::Option, Some(_)) = (&return_kind, &retry) {
+        return Err(RetryConfigurationError::new_spanned(
+            result_type.span(),
+            String::from(
+                "`retry` configuration option is supported only for functions returning `Result`",
+            ),
+        ));
+    }
+
+    if let (false, Some(cancel)) = (is_async, &cancel) {
+        return Err(RetryConfigurationError::new_spanned(
+            cancel.span(),
+            String::from("`cancel` configuration option is supported only for async functions"),
+        ));
+    }
+
+    // with `wrap_body=false` attempt isn't a separate future, so it can't be raced against cancellation.
+    if let (false, Some(cancel)) = (wrap_body, &cancel) {
+        return Err(RetryConfigurationError::new_spanned(
+            cancel.span(),
+            String::from(
+                "`cancel` configuration option can't be used together with `wrap_body=false`",
+            ),
+        ));
+    }
+
+    if let (true, Some(abort)) = (is_async, &abort) {
+        return Err(RetryConfigurationError::new_spanned(
+            abort.span(),
+            String::from("`abort` configuration option is supported only for sync functions, async functions can use `cancel` option"),
+        ));
+    }
+
+    // generated variables use `mixed_site` span, so they are not visible from function body and
+    // don't conflict with its variables. Traits are called by fully qualified path instead of `use`.
+    let span = Span::mixed_site();
+    let krate = &prepare_crate_path(crate_path);
+
+    // `impl Trait` can't be used in type annotations of variables and closures, so such type is inferred.
+    let (result_annotation, closure_annotation) = if contains_impl_trait(result_type) {
+        (quote!(), quote!())
+    } else {
+        (quote!(: #result_type), quote!(-> #result_type))
+    };
+
+    // body is evaluated in closure (or async block) so `return` and `?` finish only the current attempt.
+    // with `cancel` option attempt is raced against cancellation, so it must be a separate future.
+    let attempt = match (wrap_body, is_async, &cancel) {
+        (false, ..) => attempt,
+        (true, false, _) => quote_spanned!(span=> (|| #closure_annotation #attempt)()),
+        (true, true, None) => quote_spanned!(span=> async #attempt.await),
+        (true, true, Some(_)) => quote_spanned!(span=>
+            match retrying_cancel.run(async #attempt).await {
+                ::core::option::Option::Some(retrying_result) => retrying_result,
+                ::core::option::Option::None => {
+                    retrying_metrics.record_cancelled(&retrying_context);
+                    break #krate::cancel::Cancellable::cancelled()
+                }
+            }
+        ),
+    };
+
+    let let_retrying_cancel = cancel.map_or(quote!(), |cancel| {
+        quote_spanned!(span=> let mut retrying_cancel = #krate::cancel::Cancellation::new(#cancel);)
     });
 
+    let (let_retrying_policy, let_retrying_stop, let_retrying_wait) = match (policy, envs_prefix) {
+        (Some(name), envs_prefix) => (
+            prepare_policy(
+                krate,
+                &name,
+                stop,
+                wait,
+                envs_prefix.as_deref(),
+                envs_strict,
+            ),
+            quote_spanned!(span=> retrying_policy.stop()),
+            quote_spanned!(span=> retrying_policy.wait()),
+        ),
+        (None, Some(prefix)) => (
+            prepare_env_policy(krate, stop, wait, &prefix, envs_strict),
+            quote_spanned!(span=> retrying_policy.stop()),
+            quote_spanned!(span=> retrying_policy.wait()),
+        ),
+        (None, None) => (
+            quote!(),
+            stop.map_or(quote!(), |stop| {
+                let stop = prepare_stop(krate, stop);
+                quote!(&#stop)
+            }),
+            wait.map_or(quote!(), |wait| {
+                let wait = prepare_wait(krate, wait);
+                quote!(&#wait)
+            }),
+        ),
+    };
+
+    let let_retrying_stop = if let_retrying_stop.is_empty() {
+        quote!()
+    } else {
+        quote_spanned!(span=> let retrying_stop = #let_retrying_stop;)
+    };
+
     let retrying_stop_check = if let_retrying_stop.is_empty() {
         quote!(true)
     } else {
-        quote!(!retrying_stop.stop_execution(&retrying_context))
+        quote_spanned!(span=> !#krate::stop::Stop::stop_execution(retrying_stop, &retrying_context))
+    };
+
+    let let_retrying_wait = if let_retrying_wait.is_empty() {
+        quote!()
+    } else {
+        quote_spanned!(span=> let retrying_wait = #let_retrying_wait;)
     };
 
-    let let_retrying_wait = wait.map_or(quote!(), |w| {
-        let wait = prepare_wait(w, envs_prefix.clone());
-        quote!(
-            use ::retrying::wait::Wait;
-            let retrying_wait = #wait;
+    let retrying_sleep = if !let_retrying_cancel.is_empty() {
+        quote_spanned!(span=>
+            if retrying_cancel.run(#krate::sleep_async(retrying_wait_duration)).await.is_none() {
+                retrying_metrics.record_cancelled(&retrying_context);
+                break #krate::cancel::Cancellable::cancelled();
+            }
         )
-    });
+    } else if is_async {
+        quote_spanned!(span=> #krate::sleep_async(retrying_wait_duration).await;)
+    } else if let Some(abort) = &abort {
+        quote_spanned!(span=>
+            if !#krate::abort::RetryAbort::sleep(&#abort, retrying_wait_duration) {
+                retrying_metrics.record(&retrying_context, &#krate::Retryable::outcome(&retrying_result));
+                break retrying_result
+            }
+        )
+    } else {
+        quote_spanned!(span=> #krate::sleep_sync(retrying_wait_duration);)
+    };
+    let retrying_wait = if let_retrying_wait.is_empty() {
+        quote_spanned!(span=> retrying_metrics.record_retry(&retrying_context, #krate::Duration::ZERO);)
+    } else {
+        quote_spanned!(span=>
+            let retrying_wait_duration = #krate::wait::Wait::wait_duration(retrying_wait, &retrying_context);
+            retrying_metrics.record_retry(&retrying_context, retrying_wait_duration);
+            retrying_context.add_wait(retrying_wait_duration);
+            #retrying_sleep
+        )
+    };
 
-    let retrying_wait = if !let_retrying_wait.is_empty() && asyncness.is_some() {
-        quote!(::retrying::sleep_async(retrying_wait.wait_duration(&retrying_context)).await;)
-    } else if !let_retrying_wait.is_empty() && asyncness.is_none() {
-        quote!(::retrying::sleep_sync(retrying_wait.wait_duration(&retrying_context));)
+    let let_retrying_metrics = prepare_metrics(krate, &sig.ident, metrics_label);
+
+    let retry_err_check = retry.map_or(quote!(), |retry| prepare_retry(krate, retry));
+
+    let retrying_err = if retry_err_check.is_empty() {
+        quote!(_)
     } else {
-        quote!()
+        quote_spanned!(span=> err)
     };
 
-    let retry_err_check = retry.map_or(quote!(), prepare_retry);
+    // with `abort` option the last result is kept during wait, so it can be returned when retries are aborted.
+    let (retrying_stop_check, retry_err_check, retrying_wait) = match &abort {
+        Some(abort) => (
+            quote_spanned!(span=> #retrying_stop_check && !#krate::abort::RetryAbort::is_aborted(&#abort)),
+            quote_spanned!(span=>
+                #retry_err_check
+                retrying_context.add_attempt();
+                #retrying_wait
+            ),
+            quote!(),
+        ),
+        None => (
+            retrying_stop_check,
+            retry_err_check,
+            quote_spanned!(span=>
+                retrying_context.add_attempt();
+                #retrying_wait
+            ),
+        ),
+    };
 
-    quote!(
-    #(#attrs) *
-    #vis #constness #unsafety #asyncness #abi #fn_token #ident<#gen_params>(#params #variadic) #return_type
-    #where_clause
-    {
-        let mut retrying_context = ::retrying::RetryingContext::new();
+    let retrying_body = quote_spanned!(span=>
+        let mut retrying_context = #krate::RetryingContext::new();
+        #let_retrying_metrics
+        #let_retrying_policy
         #let_retrying_stop
         #let_retrying_wait
+        #let_retrying_cancel
 
         loop {
-            match #block {
-                Ok(result) => return Ok(result),
-                Err(err) if #retrying_stop_check => {
-                    #retry_err_check
-                    retrying_context.add_attempt();
-                    #retrying_wait
-                },
-                Err(err) => break Err(err)
+            {
+                let retrying_result #result_annotation = #attempt;
+                match #krate::Retryable::outcome(&retrying_result) {
+                    #krate::Outcome::Retry(#retrying_err) if #retrying_stop_check => {
+                        #retry_err_check
+                    },
+                    retrying_outcome => {
+                        retrying_metrics.record(&retrying_context, &retrying_outcome);
+                        break retrying_result
+                    }
+                }
+            }
+            #retrying_wait
+        }
+    );
+    let retrying_body = match boxed_future {
+        Some((pin, ..)) => quote!(#pin(async move { #retrying_body })),
+        None => retrying_body,
+    };
+
+    Ok(quote!(
+    #(#attrs) *
+    #vis #sig
+    {
+        #retrying_body
+    }))
+}
+
+/// returns invocation of macros generated by `retrying::policy!` for `policy`. The macros applies `retry` macros
+/// with policy configuration followed by `args` (so they override policy options) to `item`.
+pub(crate) fn add_policy_into_function(
+    mut policy: syn::Path,
+    args: TokenStream,
+    item: TokenStream,
+) -> TokenStream {
+    if let Some(segment) = policy.segments.last_mut() {
+        segment.ident = policy_macro_ident(&segment.ident);
+    }
+    quote!(#policy! { [#args] #item })
+}
+
+/// returns constant with policy strategies and macros that applies `retry` macros with policy configuration.
+pub(crate) fn define_policy(definition: PolicyDefinition) -> TokenStream {
+    let PolicyDefinition {
+        vis,
+        ident,
+        args,
+        config,
+    } = definition;
+
+    let krate = &prepare_crate_path(config.crate_path);
+    let stop = prepare_stop_strategy(krate, config.stop);
+    let wait = prepare_wait_strategy(krate, config.wait);
+    let args = args.iter();
+    let macro_ident = policy_macro_ident(&ident);
+
+    // `macro_rules!` macros can be used by other crates only with `#[macro_export]` that places them
+    // into the crate root, so exported macros get name that doesn't conflict with `use` in the module.
+    let (macro_export, defined_ident, use_macro) = match vis {
+        syn::Visibility::Public(_) => {
+            let exported_ident = quote::format_ident!("__retrying_exported_policy_{}", ident);
+            (
+                quote!(#[macro_export]),
+                exported_ident.clone(),
+                quote!(#vis use #exported_ident as #macro_ident;),
+            )
+        }
+        _ => (
+            quote!(),
+            macro_ident.clone(),
+            quote!(#vis use #macro_ident;),
+        ),
+    };
+
+    quote!(
+        #vis const #ident: #krate::PolicyDefinition = #krate::PolicyDefinition::new(#stop, #wait);
+
+        #[doc(hidden)]
+        #macro_export
+        macro_rules! #defined_ident {
+            ([$($args:tt)*] $($item:tt)*) => {
+                #[#krate::retry(#(#args,)* $($args)*)]
+                $($item)*
+            };
+        }
+
+        #[doc(hidden)]
+        #[allow(unused_imports)]
+        #use_macro
+    )
+}
+
+fn policy_macro_ident(ident: &syn::Ident) -> syn::Ident {
+    quote::format_ident!("__retrying_policy_{}", ident)
+}
+
+fn prepare_crate_path(crate_path: Option<String>) -> TokenStream {
+    crate_path.map_or(quote!(::retrying), |path| {
+        let path: syn::Path = syn::parse_str(&path).unwrap();
+        quote!(#path)
+    })
+}
+
+/// returns type returned by function or error if function doesn't return anything.
+fn result_type(
+    return_type: &ReturnType,
+    function_span: Span,
+) -> Result<&syn::Type, RetryConfigurationError> {
+    match return_type {
+        ReturnType::Type(_, ty) => Ok(ty.as_ref()),
+        ReturnType::Default => Err(unsupported_return_type(function_span)),
+    }
+}
+
+/// checks that function returns `Option` or `Result`.
+/// Any other type path is treated as `Result` alias (like `io::Result<T>`) or custom `retrying::Retryable` type.
+fn return_kind(result_type: &syn::Type) -> Result<ReturnKind, RetryConfigurationError> {
+    match result_type {
+        syn::Type::Paren(syn::TypeParen { elem, .. })
+        | syn::Type::Group(syn::TypeGroup { elem, .. }) => return_kind(elem),
+        syn::Type::Path(syn::TypePath { path, .. }) => match path.segments.last() {
+            Some(segment) if segment.ident == "Option" => Ok(ReturnKind::Option),
+            _ => Ok(ReturnKind::Result),
+        },
+        syn::Type::ImplTrait(_) => Ok(ReturnKind::Result),
+        _ => Err(unsupported_return_type(result_type.span())),
+    }
+}
+
+fn contains_impl_trait(ty: &syn::Type) -> bool {
+    fn contains(tokens: TokenStream) -> bool {
+        tokens.into_iter().any(|token| match token {
+            TokenTree::Ident(ident) => ident == "impl",
+            TokenTree::Group(group) => contains(group.stream()),
+            _ => false,
+        })
+    }
+    contains(ty.to_token_stream())
+}
+
+fn unsupported_return_type(span: Span) -> RetryConfigurationError {
+    RetryConfigurationError::new_spanned(
+        span,
+        String::from("`retry` macros supports only functions returning `Result` or `Option`"),
+    )
+}
+
+/// returns function that pins future, future output type and async block for functions returning
+/// `Pin<Box<dyn Future<Output = T>>>` with `Box::pin(async move { .. })` body (for example, methods transformed by `#[async_trait]`).
+fn boxed_future<'a>(
+    return_type: &'a ReturnType,
+    block: &'a syn::Block,
+) -> Result<Option<(&'a syn::Expr, &'a syn::Type, &'a syn::Block)>, RetryConfigurationError> {
+    let output = match return_type {
+        ReturnType::Type(_, ty) => generic_argument(ty, "Pin")
+            .and_then(|ty| generic_argument(ty, "Box"))
+            .and_then(future_output),
+        ReturnType::Default => None,
+    };
+    let output = match output {
+        Some(output) => output,
+        None => return Ok(None),
+    };
+
+    match block.stmts.as_slice() {
+        [syn::Stmt::Expr(syn::Expr::Call(syn::ExprCall { func, args, .. }), None)] => {
+            match args.iter().collect::<Vec<_>>().as_slice() {
+                [syn::Expr::Async(syn::ExprAsync {
+                    capture: Some(_),
+                    block,
+                    ..
+                })] => Ok(Some((func.as_ref(), output, block))),
+                _ => Err(unsupported_boxed_future(block.span())),
+            }
+        }
+        _ => Err(unsupported_boxed_future(block.span())),
+    }
+}
+
+fn unsupported_boxed_future(span: Span) -> RetryConfigurationError {
+    RetryConfigurationError::new_spanned(
+        span,
+        String::from("`retry` macros supports functions returning `Pin<Box<dyn Future>>` only with `Box::pin(async move { .. })` body"),
+    )
+}
+
+/// returns the first generic type argument of type with `name` (for example, `T` for `Box<T>`).
+fn generic_argument<'a>(ty: &'a syn::Type, name: &str) -> Option<&'a syn::Type> {
+    let segment = match ty {
+        syn::Type::Path(syn::TypePath { path, qself: None }) => path.segments.last()?,
+        _ => return None,
+    };
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) if segment.ident == name => {
+            args.args.iter().find_map(|arg| match arg {
+                syn::GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// returns `T` for `dyn Future<Output = T>`.
+fn future_output(ty: &syn::Type) -> Option<&syn::Type> {
+    let bounds = match ty {
+        syn::Type::TraitObject(syn::TypeTraitObject { bounds, .. }) => bounds,
+        _ => return None,
+    };
+    bounds.iter().find_map(|bound| {
+        let segment = match bound {
+            syn::TypeParamBound::Trait(syn::TraitBound { path, .. }) => path.segments.last()?,
+            _ => return None,
+        };
+        match &segment.arguments {
+            syn::PathArguments::AngleBracketed(args) if segment.ident == "Future" => {
+                args.args.iter().find_map(|arg| match arg {
+                    syn::GenericArgument::AssocType(syn::AssocType { ident, ty, .. })
+                        if ident == "Output" =>
+                    {
+                        Some(ty)
+                    }
+                    _ => None,
+                })
             }
+            _ => None,
         }
     })
 }
 
-fn prepare_stop(config: StopConfig, envs_prefix: Option<String>) -> TokenStream {
-    let StopConfig { attempts, duration } = config;
+/// checks function parameters, because function body is executed on every attempt and can't move them.
+/// With `clone_args` returns statements that clone parameters taken by value on every attempt
+/// and parameters without `mut` (cloned value is mutable instead of parameter).
+fn prepare_args(
+    params: Punctuated<FnArg, Token![,]>,
+    clone_args: bool,
+) -> Result<(Punctuated<FnArg, Token![,]>, TokenStream), RetryConfigurationError> {
+    let mut let_cloned_args = quote!();
+    let mut prepared_params = Punctuated::new();
 
-    match (envs_prefix, attempts, duration) {
-        (Some(prefix), Some(attempts), None) => {
-            quote!(::retrying::stop::StopAttempts::new(::retrying::override_by_env::<u32>(#attempts, #prefix, ::retrying::envs::RETRYING_STOP_ATTEMPTS)))
+    for mut param in params.into_pairs() {
+        match param.value_mut() {
+            FnArg::Receiver(receiver)
+                if receiver.reference.is_none()
+                    && !matches!(receiver.ty.as_ref(), syn::Type::Reference(_)) =>
+            {
+                return Err(RetryConfigurationError::new_spanned(
+                    receiver.self_token.span,
+                    String::from("`retry` macros can't be applied to methods taking `self` by value, because function body is executed on every attempt. Use `&self` or `&mut self` instead"),
+                ));
+            }
+            FnArg::Typed(syn::PatType { pat, ty, .. })
+                if clone_args && !matches!(ty.as_ref(), syn::Type::Reference(_)) =>
+            {
+                match pat.as_mut() {
+                    syn::Pat::Ident(syn::PatIdent {
+                        ident,
+                        mutability,
+                        by_ref: None,
+                        subpat: None,
+                        ..
+                    }) => {
+                        let_cloned_args.extend(quote!(
+                            let #mutability #ident = ::core::clone::Clone::clone(&#ident);
+                        ));
+                        *mutability = None;
+                    }
+                    pat => {
+                        return Err(RetryConfigurationError::new_spanned(
+                            pat.span(),
+                            String::from("`clone_args` configuration option supports only parameters with simple names (for example `name: String`)"),
+                        ));
+                    }
+                }
+            }
+            _ => (),
         }
-        (Some(prefix), None, Some(duration)) => {
-            quote!(::retrying::stop::StopDuration::new(::retrying::override_by_env::<f32>(#duration, #prefix, ::retrying::envs::RETRYING_STOP_DURATION)))
+        prepared_params.extend(Some(param));
+    }
+    Ok((prepared_params, let_cloned_args))
+}
+
+/// returns statement that takes shared policy with `name` from global `PolicyRegistry` through static per-function slot.
+/// Stop and wait configuration (optionally with OS environment variables) is used as default policy, its code is
+/// passed as configuration description, so functions with the same `name` and different configuration are reported.
+fn prepare_policy(
+    krate: &TokenStream,
+    name: &str,
+    stop: Option<StopConfig>,
+    wait: Option<WaitConfig>,
+    envs_prefix: Option<&str>,
+    envs_strict: bool,
+) -> TokenStream {
+    let stop = match (stop, envs_prefix) {
+        (stop, Some(prefix)) => prepare_stop_from_env(krate, stop, prefix, envs_strict),
+        (Some(stop), None) => {
+            let stop = prepare_stop(krate, stop);
+            quote!(::std::boxed::Box::new(#stop))
         }
-        (Some(prefix), Some(attempts), Some(duration)) => {
-            quote!(::retrying::stop::StopAttemptsOrDuration::new(
-                    ::retrying::override_by_env::<u32>(#attempts, #prefix, ::retrying::envs::RETRYING_STOP_ATTEMPTS),
-                    ::retrying::override_by_env::<f32>(#duration, #prefix, ::retrying::envs::RETRYING_STOP_DURATION)
-                )
-            )
+        (None, None) => quote!(::std::boxed::Box::new(#krate::stop::StopNever {})),
+    };
+    let wait = match (wait, envs_prefix) {
+        (wait, Some(prefix)) => prepare_wait_from_env(krate, wait, prefix, envs_strict),
+        (Some(wait), None) => {
+            let wait = prepare_wait(krate, wait);
+            quote!(::std::boxed::Box::new(#wait))
         }
-        (None, Some(attempts), None) => {
-            quote!(::retrying::stop::StopAttempts::new(#attempts))
+        (None, None) => quote!(::std::boxed::Box::new(#krate::wait::WaitFixed::new(
+            #krate::Duration::ZERO
+        ))),
+    };
+
+    let config = quote!(#stop, #wait).to_string();
+
+    quote_spanned!(Span::mixed_site()=>
+        let retrying_policy = {
+            static RETRYING_POLICY: #krate::PolicySlot = #krate::PolicySlot::new();
+            RETRYING_POLICY.get_or_insert_with(#krate::PolicyRegistry::global(), #name, #config, || #krate::Policy::new(#stop, #wait))
+        };
+    )
+}
+
+/// returns statement that takes policy built from OS environment variables from static per-function cache,
+/// so OS environment variables are read only on the first call (or after `retrying::reload_env()`).
+fn prepare_env_policy(
+    krate: &TokenStream,
+    stop: Option<StopConfig>,
+    wait: Option<WaitConfig>,
+    envs_prefix: &str,
+    envs_strict: bool,
+) -> TokenStream {
+    let stop = prepare_stop_from_env(krate, stop, envs_prefix, envs_strict);
+    let wait = prepare_wait_from_env(krate, wait, envs_prefix, envs_strict);
+
+    quote_spanned!(Span::mixed_site()=>
+        let retrying_policy = {
+            static RETRYING_ENVS: #krate::envs::EnvCache = #krate::envs::EnvCache::new();
+            RETRYING_ENVS.get_or_load(|| #krate::Policy::new(#stop, #wait))
+        };
+    )
+}
+
+/// returns statement that takes static metrics of function (metrics are collected only with `metrics` feature).
+fn prepare_metrics(krate: &TokenStream, ident: &syn::Ident, label: Option<String>) -> TokenStream {
+    let function = ident.to_string();
+    let label = quote_option(label);
+
+    quote_spanned!(Span::mixed_site()=>
+        let retrying_metrics = {
+            static RETRYING_METRICS: #krate::metrics::FunctionMetrics = #krate::metrics::FunctionMetrics::new(#function, #label);
+            &RETRYING_METRICS
+        };
+    )
+}
+
+fn prepare_stop(krate: &TokenStream, config: StopConfig) -> TokenStream {
+    let StopConfig {
+        attempts,
+        duration,
+        when,
+    } = config;
+    let duration = duration.map(|duration| quote_duration(krate, duration));
+    let when = when.map(|when| prepare_stop_fn(krate, &when));
+
+    let stop = match (attempts, duration) {
+        (Some(attempts), None) => {
+            quote!(#krate::stop::StopAttempts::new(#attempts))
         }
-        (None, None, Some(duration)) => {
-            quote!(::retrying::stop::StopDuration::new(#duration))
+        (None, Some(duration)) => {
+            quote!(#krate::stop::StopDuration::new(#duration))
         }
-        (None, Some(attempts), Some(duration)) => {
-            quote!(::retrying::stop::StopAttemptsOrDuration::new(#attempts, #duration))
+        (Some(attempts), Some(duration)) => {
+            quote!(#krate::stop::StopAttemptsOrDuration::new(#attempts, #duration))
         }
-        _ => quote!(::retrying::stop::StopNever {}),
+        (None, None) => return when.unwrap_or_else(|| quote!(#krate::stop::StopNever {})),
+    };
+    match when {
+        Some(when) => quote!(#krate::stop::StopOr::new(#stop, #when)),
+        None => stop,
+    }
+}
+
+/// returns `retrying::stop::StopFn` that calls function from `stop=when(path)` configuration.
+fn prepare_stop_fn(krate: &TokenStream, when: &str) -> TokenStream {
+    let when: TokenStream = syn::parse_str(when).unwrap();
+    quote!(#krate::stop::StopFn::new(#when))
+}
+
+fn prepare_stop_from_env(
+    krate: &TokenStream,
+    config: Option<StopConfig>,
+    envs_prefix: &str,
+    envs_strict: bool,
+) -> TokenStream {
+    let when = config
+        .as_ref()
+        .and_then(|config| config.when.as_deref())
+        .map(|when| prepare_stop_fn(krate, when));
+    let strategy = prepare_stop_strategy(krate, config);
+    let envs_errors = prepare_envs_errors(krate, envs_strict);
+    let stop = quote!(#krate::envs::stop_from_env(#envs_prefix, #strategy, #envs_errors));
+
+    // OS environment variables override only attempts and duration, the function is always checked
+    match when {
+        Some(when) => quote!(::std::boxed::Box::new(#krate::stop::StopOr::new(#stop, #when))),
+        None => stop,
     }
 }
 
-fn prepare_wait(config: WaitConfig, envs_prefix: Option<String>) -> TokenStream {
-    match (config, envs_prefix) {
-        (WaitConfig::Fixed { seconds }, Some(prefix)) => {
-            quote!(::retrying::wait::WaitFixed::new(::retrying::override_by_env::<f32>(#seconds, #prefix, ::retrying::envs::RETRYING_WAIT_FIXED)))
+/// returns `retrying::stop::StopStrategy` built from configuration.
+fn prepare_stop_strategy(krate: &TokenStream, config: Option<StopConfig>) -> TokenStream {
+    let (attempts, duration) = config.map_or((None, None), |c| (c.attempts, c.duration));
+    let attempts = quote_option(attempts);
+    let duration = quote_option(duration.map(|duration| quote_duration(krate, duration)));
+
+    quote!(#krate::stop::StopStrategy {
+        attempts: #attempts,
+        duration: #duration
+    })
+}
+
+fn prepare_wait(krate: &TokenStream, config: WaitConfig) -> TokenStream {
+    match config {
+        WaitConfig::Fixed { duration } => {
+            let duration = quote_duration(krate, duration);
+            quote!(#krate::wait::WaitFixed::new(#duration))
+        }
+        WaitConfig::Random { min, max } => {
+            let (min, max) = (quote_duration(krate, min), quote_duration(krate, max));
+            quote!(#krate::wait::WaitRandom::new(#min, #max))
         }
-        (WaitConfig::Fixed { seconds }, None) => quote!(::retrying::wait::WaitFixed::new(#seconds)),
+        WaitConfig::Exponential {
+            multiplier,
+            min,
+            max,
+            exp_base,
+        } => {
+            let (multiplier, min, max) = (
+                quote_duration(krate, multiplier),
+                quote_duration(krate, min),
+                quote_duration(krate, max),
+            );
+            quote!(#krate::wait::WaitExponential::new(#multiplier, #min, #max, #exp_base))
+        }
+    }
+}
 
-        (WaitConfig::Random { min, max }, Some(prefix)) => {
-            quote!(::retrying::wait::WaitRandom::new(
-                ::retrying::override_by_env::<f32>(#min, #prefix, ::retrying::envs::RETRYING_WAIT_RANDOM_MIN),
-                ::retrying::override_by_env::<f32>(#max, #prefix, ::retrying::envs::RETRYING_WAIT_RANDOM_MAX)
-            ))
+fn prepare_wait_from_env(
+    krate: &TokenStream,
+    config: Option<WaitConfig>,
+    envs_prefix: &str,
+    envs_strict: bool,
+) -> TokenStream {
+    let strategy = prepare_wait_strategy(krate, config);
+    let envs_errors = prepare_envs_errors(krate, envs_strict);
+
+    quote!(#krate::envs::wait_from_env(#envs_prefix, #strategy, #envs_errors))
+}
+
+/// returns `Option<retrying::wait::WaitStrategy>` built from configuration.
+fn prepare_wait_strategy(krate: &TokenStream, config: Option<WaitConfig>) -> TokenStream {
+    match config {
+        Some(WaitConfig::Fixed { duration }) => {
+            let duration = quote_duration(krate, duration);
+            quote!(::core::option::Option::Some(#krate::wait::WaitStrategy::Fixed { duration: #duration }))
         }
-        (WaitConfig::Random { min, max }, None) => {
-            quote!(::retrying::wait::WaitRandom::new(#min, #max))
+        Some(WaitConfig::Random { min, max }) => {
+            let (min, max) = (quote_duration(krate, min), quote_duration(krate, max));
+            quote!(::core::option::Option::Some(#krate::wait::WaitStrategy::Random { min: #min, max: #max }))
         }
-        (
-            WaitConfig::Exponential {
-                multiplier,
-                min,
-                max,
-                exp_base,
-            },
-            Some(prefix),
-        ) => quote!(::retrying::wait::WaitExponential::new(
-            ::retrying::override_by_env::<f32>(#multiplier, #prefix, ::retrying::envs::RETRYING_WAIT_EXPONENTIAL_MULTIPLIER),
-            ::retrying::override_by_env::<f32>(#min, #prefix, ::retrying::envs::RETRYING_WAIT_EXPONENTIAL_MIN),
-            ::retrying::override_by_env::<f32>(#max, #prefix, ::retrying::envs::RETRYING_WAIT_EXPONENTIAL_MAX),
-            ::retrying::override_by_env::<u32>(#exp_base, #prefix, ::retrying::envs::RETRYING_WAIT_EXPONENTIAL_EXP_BASE)
-        )),
-        (
-            WaitConfig::Exponential {
-                multiplier,
-                min,
-                max,
-                exp_base,
-            },
-            None,
-        ) => quote!(::retrying::wait::WaitExponential::new(#multiplier, #min, #max, #exp_base)),
+        Some(WaitConfig::Exponential {
+            multiplier,
+            min,
+            max,
+            exp_base,
+        }) => {
+            let (multiplier, min, max) = (
+                quote_duration(krate, multiplier),
+                quote_duration(krate, min),
+                quote_duration(krate, max),
+            );
+            quote!(::core::option::Option::Some(#krate::wait::WaitStrategy::Exponential {
+                multiplier: #multiplier,
+                min: #min,
+                max: #max,
+                exp_base: #exp_base
+            }))
+        }
+        None => quote!(::core::option::Option::None),
+    }
+}
+
+fn prepare_envs_errors(krate: &TokenStream, envs_strict: bool) -> TokenStream {
+    if envs_strict {
+        quote!(#krate::envs::EnvErrors::Panic)
+    } else {
+        quote!(#krate::envs::env_errors())
+    }
+}
+
+fn quote_duration(krate: &TokenStream, duration: Duration) -> TokenStream {
+    let secs = duration.as_secs();
+    let nanos = duration.subsec_nanos();
+    quote!(#krate::Duration::new(#secs, #nanos))
+}
+
+fn quote_option<T: quote::ToTokens>(value: Option<T>) -> TokenStream {
+    match value {
+        Some(value) => quote!(::core::option::Option::Some(#value)),
+        None => quote!(::core::option::Option::None),
     }
 }
 
-fn prepare_retry(config: RetryConfig) -> TokenStream {
+fn prepare_retry(krate: &TokenStream, config: RetryConfig) -> TokenStream {
     let RetryConfig {
         if_errors,
         if_not_errors,
     } = config;
 
     let if_error_check = if_errors.is_some();
+    let span = Span::mixed_site();
+    let break_not_retryable = quote_spanned!(span=> {
+        retrying_metrics.record(&retrying_context, &#krate::Outcome::Abort(err));
+        break retrying_result
+    });
 
     if let Some(errors) = if_errors.or(if_not_errors) {
         let errors_check = errors
@@ -188,16 +779,16 @@ fn prepare_retry(config: RetryConfig) -> TokenStream {
             .reduce(|acc: TokenStream, v: TokenStream| quote!(#acc | #v));
 
         if if_error_check {
-            quote!(
+            quote_spanned!(span=>
                 match err {
                     #errors_check => (),
-                    _ => break Err(err)
+                    _ => #break_not_retryable
                 };
             )
         } else {
-            quote!(
+            quote_spanned!(span=>
                 match err {
-                    #errors_check => break Err(err),
+                    #errors_check => #break_not_retryable,
                     _ => ()
                 };
             )
@@ -213,12 +804,14 @@ mod tests {
 
     #[test]
     fn test_prepare_stop() {
+        let krate = quote!(::retrying);
         let result = prepare_stop(
+            &krate,
             StopConfig {
                 attempts: Some(1),
                 duration: None,
+                when: None,
             },
-            None,
         );
         assert_eq!(
             result.to_string(),
@@ -226,139 +819,336 @@ mod tests {
         );
 
         let result = prepare_stop(
+            &krate,
             StopConfig {
-                attempts: Some(1),
-                duration: None,
+                attempts: None,
+                duration: Some(Duration::from_millis(1500)),
+                when: None,
             },
-            Some("TEST".to_string()),
         );
-        assert_eq!(result.to_string(), ":: retrying :: stop :: StopAttempts :: new (:: retrying :: override_by_env :: < u32 > (1u32 , \"TEST\" , :: retrying :: envs :: RETRYING_STOP_ATTEMPTS))");
+        assert_eq!(
+            result.to_string(),
+            ":: retrying :: stop :: StopDuration :: new (:: retrying :: Duration :: new (1u64 , 500000000u32))"
+        );
 
         let result = prepare_stop(
+            &krate,
             StopConfig {
-                attempts: None,
-                duration: Some(1.5),
+                attempts: Some(1),
+                duration: Some(Duration::from_millis(500)),
+                when: None,
             },
-            None,
         );
         assert_eq!(
             result.to_string(),
-            ":: retrying :: stop :: StopDuration :: new (1.5f32)"
+            ":: retrying :: stop :: StopAttemptsOrDuration :: new (1u32 , :: retrying :: Duration :: new (0u64 , 500000000u32))"
         );
 
         let result = prepare_stop(
+            &quote!(retry_lib),
             StopConfig {
                 attempts: None,
-                duration: Some(1.5),
+                duration: Some(Duration::from_secs(2)),
+                when: None,
             },
-            Some("TEST".to_string()),
         );
-        assert_eq!(result.to_string(), ":: retrying :: stop :: StopDuration :: new (:: retrying :: override_by_env :: < f32 > (1.5f32 , \"TEST\" , :: retrying :: envs :: RETRYING_STOP_DURATION))");
+        assert_eq!(
+            result.to_string(),
+            "retry_lib :: stop :: StopDuration :: new (retry_lib :: Duration :: new (2u64 , 0u32))"
+        );
 
         let result = prepare_stop(
+            &krate,
             StopConfig {
-                attempts: Some(1),
-                duration: Some(0.5),
+                attempts: None,
+                duration: None,
+                when: Some(String::from("crate :: is_shutdown")),
             },
-            None,
         );
         assert_eq!(
             result.to_string(),
-            ":: retrying :: stop :: StopAttemptsOrDuration :: new (1u32 , 0.5f32)"
+            ":: retrying :: stop :: StopFn :: new (crate :: is_shutdown)"
         );
 
         let result = prepare_stop(
+            &krate,
             StopConfig {
-                attempts: Some(1),
-                duration: Some(0.5),
+                attempts: Some(3),
+                duration: None,
+                when: Some(String::from("is_shutdown")),
             },
-            Some("TEST".to_string()),
         );
-        assert_eq!(result.to_string(), ":: retrying :: stop :: StopAttemptsOrDuration :: new (\
-            :: retrying :: override_by_env :: < u32 > (1u32 , \"TEST\" , :: retrying :: envs :: RETRYING_STOP_ATTEMPTS) , \
-            :: retrying :: override_by_env :: < f32 > (0.5f32 , \"TEST\" , :: retrying :: envs :: RETRYING_STOP_DURATION))");
+        assert_eq!(
+            result.to_string(),
+            ":: retrying :: stop :: StopOr :: new (:: retrying :: stop :: StopAttempts :: new (3u32) , :: retrying :: stop :: StopFn :: new (is_shutdown))"
+        );
     }
 
     #[test]
-    fn test_prepare_wait() {
-        let result = prepare_wait(WaitConfig::Fixed { seconds: 0.5 }, None);
+    fn test_prepare_stop_from_env() {
+        let krate = quote!(::retrying);
+        let result = prepare_stop_from_env(
+            &krate,
+            Some(StopConfig {
+                attempts: Some(1),
+                duration: Some(Duration::from_millis(500)),
+                when: None,
+            }),
+            "TEST",
+            false,
+        );
+        assert_eq!(result.to_string(), ":: retrying :: envs :: stop_from_env (\"TEST\" , :: retrying :: stop :: StopStrategy { \
+            attempts : :: core :: option :: Option :: Some (1u32) , \
+            duration : :: core :: option :: Option :: Some (:: retrying :: Duration :: new (0u64 , 500000000u32)) \
+        } , :: retrying :: envs :: env_errors ())");
+
+        let result = prepare_stop_from_env(&krate, None, "TEST", true);
+        assert_eq!(result.to_string(), ":: retrying :: envs :: stop_from_env (\"TEST\" , :: retrying :: stop :: StopStrategy { \
+            attempts : :: core :: option :: Option :: None , \
+            duration : :: core :: option :: Option :: None \
+        } , :: retrying :: envs :: EnvErrors :: Panic)");
+
+        let result = prepare_stop_from_env(
+            &krate,
+            Some(StopConfig {
+                attempts: Some(3),
+                duration: None,
+                when: Some(String::from("is_shutdown")),
+            }),
+            "TEST",
+            false,
+        );
         assert_eq!(
             result.to_string(),
-            ":: retrying :: wait :: WaitFixed :: new (0.5f32)"
+            ":: std :: boxed :: Box :: new (:: retrying :: stop :: StopOr :: new (\
+            :: retrying :: envs :: stop_from_env (\"TEST\" , :: retrying :: stop :: StopStrategy { \
+                attempts : :: core :: option :: Option :: Some (3u32) , \
+                duration : :: core :: option :: Option :: None \
+            } , :: retrying :: envs :: env_errors ()) , \
+            :: retrying :: stop :: StopFn :: new (is_shutdown)\
+        ))"
         );
+    }
 
-        let result = prepare_wait(WaitConfig::Fixed { seconds: 0.5 }, Some("TEST".to_string()));
-        assert_eq!(result.to_string(), ":: retrying :: wait :: WaitFixed :: new (:: retrying :: override_by_env :: < f32 > (0.5f32 , \"TEST\" , :: retrying :: envs :: RETRYING_WAIT_FIXED))");
-
+    #[test]
+    fn test_prepare_wait() {
+        let krate = quote!(::retrying);
         let result = prepare_wait(
-            WaitConfig::Random {
-                min: 0.1,
-                max: 100.0,
+            &krate,
+            WaitConfig::Fixed {
+                duration: Duration::from_millis(500),
             },
-            None,
         );
         assert_eq!(
             result.to_string(),
-            ":: retrying :: wait :: WaitRandom :: new (0.1f32 , 100f32)"
+            ":: retrying :: wait :: WaitFixed :: new (:: retrying :: Duration :: new (0u64 , 500000000u32))"
         );
 
         let result = prepare_wait(
+            &krate,
             WaitConfig::Random {
-                min: 0.1,
-                max: 100.0,
+                min: Duration::from_millis(100),
+                max: Duration::from_secs(100),
             },
-            Some("TEST".to_string()),
         );
-        assert_eq!(result.to_string(), ":: retrying :: wait :: WaitRandom :: new (\
-            :: retrying :: override_by_env :: < f32 > (0.1f32 , \"TEST\" , :: retrying :: envs :: RETRYING_WAIT_RANDOM_MIN) , \
-            :: retrying :: override_by_env :: < f32 > (100f32 , \"TEST\" , :: retrying :: envs :: RETRYING_WAIT_RANDOM_MAX))");
+        assert_eq!(
+            result.to_string(),
+            ":: retrying :: wait :: WaitRandom :: new (:: retrying :: Duration :: new (0u64 , 100000000u32) , :: retrying :: Duration :: new (100u64 , 0u32))"
+        );
 
         let result = prepare_wait(
+            &krate,
             WaitConfig::Exponential {
-                multiplier: 0.5,
-                min: 0.5,
-                max: 1.5,
+                multiplier: Duration::from_millis(500),
+                min: Duration::from_millis(500),
+                max: Duration::from_millis(1500),
                 exp_base: 2,
             },
-            None,
         );
         assert_eq!(
             result.to_string(),
-            ":: retrying :: wait :: WaitExponential :: new (0.5f32 , 0.5f32 , 1.5f32 , 2u32)"
+            ":: retrying :: wait :: WaitExponential :: new (:: retrying :: Duration :: new (0u64 , 500000000u32) , :: retrying :: Duration :: new (0u64 , 500000000u32) , :: retrying :: Duration :: new (1u64 , 500000000u32) , 2u32)"
         );
+    }
 
-        let result = prepare_wait(
-            WaitConfig::Exponential {
-                multiplier: 0.5,
-                min: 0.5,
-                max: 1.5,
+    #[test]
+    fn test_prepare_wait_from_env() {
+        let krate = quote!(::retrying);
+        let result = prepare_wait_from_env(
+            &krate,
+            Some(WaitConfig::Fixed {
+                duration: Duration::from_millis(500),
+            }),
+            "TEST",
+            false,
+        );
+        assert_eq!(result.to_string(), ":: retrying :: envs :: wait_from_env (\"TEST\" , \
+            :: core :: option :: Option :: Some (:: retrying :: wait :: WaitStrategy :: Fixed { duration : :: retrying :: Duration :: new (0u64 , 500000000u32) }) , \
+            :: retrying :: envs :: env_errors ())");
+
+        let result = prepare_wait_from_env(
+            &krate,
+            Some(WaitConfig::Random {
+                min: Duration::from_millis(100),
+                max: Duration::from_secs(100),
+            }),
+            "TEST",
+            false,
+        );
+        assert_eq!(result.to_string(), ":: retrying :: envs :: wait_from_env (\"TEST\" , \
+            :: core :: option :: Option :: Some (:: retrying :: wait :: WaitStrategy :: Random { min : :: retrying :: Duration :: new (0u64 , 100000000u32) , max : :: retrying :: Duration :: new (100u64 , 0u32) }) , \
+            :: retrying :: envs :: env_errors ())");
+
+        let result = prepare_wait_from_env(
+            &krate,
+            Some(WaitConfig::Exponential {
+                multiplier: Duration::from_millis(500),
+                min: Duration::from_millis(500),
+                max: Duration::from_millis(1500),
                 exp_base: 2,
-            },
-            Some("TEST".to_string()),
+            }),
+            "TEST",
+            false,
+        );
+        assert_eq!(result.to_string(), ":: retrying :: envs :: wait_from_env (\"TEST\" , \
+            :: core :: option :: Option :: Some (:: retrying :: wait :: WaitStrategy :: Exponential { \
+                multiplier : :: retrying :: Duration :: new (0u64 , 500000000u32) , min : :: retrying :: Duration :: new (0u64 , 500000000u32) , max : :: retrying :: Duration :: new (1u64 , 500000000u32) , exp_base : 2u32 \
+            }) , :: retrying :: envs :: env_errors ())");
+
+        let result = prepare_wait_from_env(&krate, None, "TEST", false);
+        assert_eq!(
+            result.to_string(),
+            ":: retrying :: envs :: wait_from_env (\"TEST\" , :: core :: option :: Option :: None , :: retrying :: envs :: env_errors ())"
+        );
+    }
+
+    #[test]
+    fn test_prepare_policy() {
+        let krate = quote!(::retrying);
+        let result = prepare_policy(
+            &krate,
+            "database",
+            Some(StopConfig {
+                attempts: Some(1),
+                duration: None,
+                when: None,
+            }),
+            None,
+            None,
+            false,
+        );
+        let stop =
+            ":: std :: boxed :: Box :: new (:: retrying :: stop :: StopAttempts :: new (1u32))";
+        let wait = ":: std :: boxed :: Box :: new (:: retrying :: wait :: WaitFixed :: new (:: retrying :: Duration :: ZERO))";
+        assert_eq!(
+            result.to_string(),
+            format!(
+                "let retrying_policy = {{ \
+                    static RETRYING_POLICY : :: retrying :: PolicySlot = :: retrying :: PolicySlot :: new () ; \
+                    RETRYING_POLICY . get_or_insert_with (:: retrying :: PolicyRegistry :: global () , \"database\" , \
+                        \"{stop} , {wait}\" , || :: retrying :: Policy :: new ({stop} , {wait})\
+                    ) \
+                }} ;",
+                stop = stop,
+                wait = wait
+            )
+        );
+
+        let result = prepare_policy(
+            &krate,
+            "database",
+            None,
+            Some(WaitConfig::Fixed {
+                duration: Duration::from_millis(500),
+            }),
+            Some("TEST"),
+            false,
+        );
+        let stop = ":: retrying :: envs :: stop_from_env (\"TEST\" , :: retrying :: stop :: StopStrategy { \
+            attempts : :: core :: option :: Option :: None , \
+            duration : :: core :: option :: Option :: None \
+        } , :: retrying :: envs :: env_errors ())";
+        let wait = ":: retrying :: envs :: wait_from_env (\"TEST\" , \
+            :: core :: option :: Option :: Some (:: retrying :: wait :: WaitStrategy :: Fixed { duration : :: retrying :: Duration :: new (0u64 , 500000000u32) }) , \
+            :: retrying :: envs :: env_errors ())";
+        assert_eq!(
+            result.to_string(),
+            format!(
+                "let retrying_policy = {{ \
+                    static RETRYING_POLICY : :: retrying :: PolicySlot = :: retrying :: PolicySlot :: new () ; \
+                    RETRYING_POLICY . get_or_insert_with (:: retrying :: PolicyRegistry :: global () , \"database\" , \
+                        {config:?} , || :: retrying :: Policy :: new ({stop} , {wait})\
+                    ) \
+                }} ;",
+                config = format!("{} , {}", stop, wait),
+                stop = stop,
+                wait = wait
+            )
+        );
+    }
+
+    #[test]
+    fn test_prepare_env_policy() {
+        let krate = quote!(::retrying);
+        let result = prepare_env_policy(
+            &krate,
+            Some(StopConfig {
+                attempts: Some(1),
+                duration: None,
+                when: None,
+            }),
+            None,
+            "TEST",
+            false,
+        );
+        assert_eq!(result.to_string(), "let retrying_policy = { \
+            static RETRYING_ENVS : :: retrying :: envs :: EnvCache = :: retrying :: envs :: EnvCache :: new () ; \
+            RETRYING_ENVS . get_or_load (|| :: retrying :: Policy :: new (\
+                :: retrying :: envs :: stop_from_env (\"TEST\" , :: retrying :: stop :: StopStrategy { \
+                    attempts : :: core :: option :: Option :: Some (1u32) , \
+                    duration : :: core :: option :: Option :: None \
+                } , :: retrying :: envs :: env_errors ()) , \
+                :: retrying :: envs :: wait_from_env (\"TEST\" , :: core :: option :: Option :: None , :: retrying :: envs :: env_errors ())\
+            )) \
+        } ;");
+    }
+
+    #[test]
+    fn test_prepare_metrics() {
+        let result = prepare_metrics(
+            &quote!(::retrying),
+            &syn::parse_quote!(my_function),
+            Some(String::from("checkout")),
         );
-        assert_eq!(result.to_string(), ":: retrying :: wait :: WaitExponential :: new (\
-            :: retrying :: override_by_env :: < f32 > (0.5f32 , \"TEST\" , :: retrying :: envs :: RETRYING_WAIT_EXPONENTIAL_MULTIPLIER) , \
-            :: retrying :: override_by_env :: < f32 > (0.5f32 , \"TEST\" , :: retrying :: envs :: RETRYING_WAIT_EXPONENTIAL_MIN) , \
-            :: retrying :: override_by_env :: < f32 > (1.5f32 , \"TEST\" , :: retrying :: envs :: RETRYING_WAIT_EXPONENTIAL_MAX) , \
-            :: retrying :: override_by_env :: < u32 > (2u32 , \"TEST\" , :: retrying :: envs :: RETRYING_WAIT_EXPONENTIAL_EXP_BASE))");
+        assert_eq!(result.to_string(), "let retrying_metrics = { \
+            static RETRYING_METRICS : :: retrying :: metrics :: FunctionMetrics = :: retrying :: metrics :: FunctionMetrics :: new (\"my_function\" , :: core :: option :: Option :: Some (\"checkout\")) ; \
+            & RETRYING_METRICS \
+        } ;");
     }
 
     #[test]
     fn test_prepare_retry() {
-        let result = prepare_retry(RetryConfig {
-            if_errors: Some(vec!["syn::Error".to_string()]),
-            if_not_errors: None,
-        });
+        let krate = quote!(::retrying);
+        let result = prepare_retry(
+            &krate,
+            RetryConfig {
+                if_errors: Some(vec!["syn::Error".to_string()]),
+                if_not_errors: None,
+            },
+        );
         assert_eq!(
             result.to_string(),
-            "match err { syn :: Error { .. } => () , _ => break Err (err) } ;"
+            "match err { syn :: Error { .. } => () , _ => { retrying_metrics . record (& retrying_context , & :: retrying :: Outcome :: Abort (err)) ; break retrying_result } } ;"
         );
 
-        let result = prepare_retry(RetryConfig {
-            if_errors: None,
-            if_not_errors: Some(vec!["syn::Error".to_string(), "::other::Error".to_string()]),
-        });
-        assert_eq!(result.to_string(), "match err { syn :: Error { .. } | :: other :: Error { .. } => break Err (err) , _ => () } ;");
+        let result = prepare_retry(
+            &krate,
+            RetryConfig {
+                if_errors: None,
+                if_not_errors: Some(vec!["syn::Error".to_string(), "::other::Error".to_string()]),
+            },
+        );
+        assert_eq!(result.to_string(), "match err { syn :: Error { .. } | :: other :: Error { .. } => { retrying_metrics . record (& retrying_context , & :: retrying :: Outcome :: Abort (err)) ; break retrying_result } , _ => () } ;");
     }
 
     #[test]
@@ -366,9 +1156,12 @@ mod tests {
         let config = RetryingConfig {
             stop: Some(StopConfig {
                 attempts: Some(1),
-                duration: Some(5.5),
+                duration: Some(Duration::from_millis(5500)),
+                when: None,
+            }),
+            wait: Some(WaitConfig::Fixed {
+                duration: Duration::from_millis(500),
             }),
-            wait: Some(WaitConfig::Fixed { seconds: 0.5 }),
             retry: Some(RetryConfig {
                 if_errors: Some(vec![
                     ":: syn :: Error".to_string(),
@@ -377,6 +1170,14 @@ mod tests {
                 if_not_errors: None,
             }),
             envs_prefix: Some(String::from("TEST")),
+            envs_strict: true,
+            policy: None,
+            clone_args: false,
+            crate_path: None,
+            wrap_body: true,
+            metrics_label: None,
+            cancel: None,
+            abort: None,
         };
 
         let function = syn::parse_quote!(
@@ -385,29 +1186,51 @@ mod tests {
             }
         );
 
-        let result = add_retry_code_into_function(function, config);
+        let result = add_retry_code_into_function(function, config).unwrap();
         let expected = "\
-        fn test_function < > (in_param : & str) -> Result < i32 , ParseIntError > { \
+        fn test_function (in_param : & str) -> Result < i32 , ParseIntError > { \
             let mut retrying_context = :: retrying :: RetryingContext :: new () ; \
-            use :: retrying :: stop :: Stop ; \
-            let retrying_stop = :: retrying :: stop :: StopAttemptsOrDuration :: new (\
-                :: retrying :: override_by_env :: < u32 > (1u32 , \"TEST\" , :: retrying :: envs :: RETRYING_STOP_ATTEMPTS) , \
-                :: retrying :: override_by_env :: < f32 > (5.5f32 , \"TEST\" , :: retrying :: envs :: RETRYING_STOP_DURATION)\
-            ) ; \
-            use :: retrying :: wait :: Wait ; \
-            let retrying_wait = :: retrying :: wait :: WaitFixed :: new (:: retrying :: override_by_env :: < f32 > (0.5f32 , \"TEST\" , :: retrying :: envs :: RETRYING_WAIT_FIXED)) ; \
-            loop { match { in_param . parse :: < i32 > () } { \
-                Ok (result) => return Ok (result) , \
-                Err (err) if ! retrying_stop . stop_execution (& retrying_context) => { \
-                    match err { \
-                        :: syn :: Error { .. } | :: std :: num :: ParseIntError { .. } => () , \
-                        _ => break Err (err) \
-                    } ; \
-                    retrying_context . add_attempt () ; \
-                    :: retrying :: sleep_sync (retrying_wait . wait_duration (& retrying_context)) ; \
-                } , \
-                Err (err) => break Err (err) \
+            let retrying_metrics = { \
+                static RETRYING_METRICS : :: retrying :: metrics :: FunctionMetrics = :: retrying :: metrics :: FunctionMetrics :: new (\"test_function\" , :: core :: option :: Option :: None) ; \
+                & RETRYING_METRICS \
+            } ; \
+            let retrying_policy = { \
+                static RETRYING_ENVS : :: retrying :: envs :: EnvCache = :: retrying :: envs :: EnvCache :: new () ; \
+                RETRYING_ENVS . get_or_load (|| :: retrying :: Policy :: new (\
+                    :: retrying :: envs :: stop_from_env (\"TEST\" , :: retrying :: stop :: StopStrategy { \
+                        attempts : :: core :: option :: Option :: Some (1u32) , \
+                        duration : :: core :: option :: Option :: Some (:: retrying :: Duration :: new (5u64 , 500000000u32)) \
+                    } , :: retrying :: envs :: EnvErrors :: Panic) , \
+                    :: retrying :: envs :: wait_from_env (\"TEST\" , \
+                        :: core :: option :: Option :: Some (:: retrying :: wait :: WaitStrategy :: Fixed { duration : :: retrying :: Duration :: new (0u64 , 500000000u32) }) , \
+                        :: retrying :: envs :: EnvErrors :: Panic)\
+                )) \
+            } ; \
+                        let retrying_stop = retrying_policy . stop () ; \
+                        let retrying_wait = retrying_policy . wait () ; \
+            loop { { \
+                let retrying_result : Result < i32 , ParseIntError > = (|| -> Result < i32 , ParseIntError > { in_param . parse :: < i32 > () }) () ; \
+                match :: retrying :: Retryable :: outcome (& retrying_result) { \
+                    :: retrying :: Outcome :: Retry (err) if ! :: retrying :: stop :: Stop :: stop_execution (retrying_stop , & retrying_context) => { \
+                        match err { \
+                            :: syn :: Error { .. } | :: std :: num :: ParseIntError { .. } => () , \
+                            _ => { \
+                                retrying_metrics . record (& retrying_context , & :: retrying :: Outcome :: Abort (err)) ; \
+                                break retrying_result \
+                            } \
+                        } ; \
+                    } , \
+                    retrying_outcome => { \
+                        retrying_metrics . record (& retrying_context , & retrying_outcome) ; \
+                        break retrying_result \
+                    } \
+                } \
             } \
+            retrying_context . add_attempt () ; \
+            let retrying_wait_duration = :: retrying :: wait :: Wait :: wait_duration (retrying_wait , & retrying_context) ; \
+            retrying_metrics . record_retry (& retrying_context , retrying_wait_duration) ; \
+            retrying_context . add_wait (retrying_wait_duration) ; \
+            :: retrying :: sleep_sync (retrying_wait_duration) ; \
         } }";
         assert_eq!(result.to_string(), expected);
     }
@@ -419,6 +1242,14 @@ mod tests {
             wait: None,
             retry: None,
             envs_prefix: None,
+            envs_strict: false,
+            policy: None,
+            clone_args: false,
+            crate_path: None,
+            wrap_body: true,
+            metrics_label: None,
+            cancel: None,
+            abort: None,
         };
 
         let function = syn::parse_quote!(
@@ -427,20 +1258,314 @@ mod tests {
             }
         );
 
-        let result = add_retry_code_into_function(function, config);
+        let result = add_retry_code_into_function(function, config).unwrap();
+
+        let expected = "\
+        fn test_function (in_param : & str) -> Result < i32 , ParseIntError > { \
+            let mut retrying_context = :: retrying :: RetryingContext :: new () ; \
+            let retrying_metrics = { \
+                static RETRYING_METRICS : :: retrying :: metrics :: FunctionMetrics = :: retrying :: metrics :: FunctionMetrics :: new (\"test_function\" , :: core :: option :: Option :: None) ; \
+                & RETRYING_METRICS \
+            } ; \
+            loop { { \
+                let retrying_result : Result < i32 , ParseIntError > = (|| -> Result < i32 , ParseIntError > { in_param . parse :: < i32 > () }) () ; \
+                match :: retrying :: Retryable :: outcome (& retrying_result) { \
+                    :: retrying :: Outcome :: Retry (_) if true => { } , \
+                    retrying_outcome => { \
+                        retrying_metrics . record (& retrying_context , & retrying_outcome) ; \
+                        break retrying_result \
+                    } \
+                } \
+            } \
+            retrying_context . add_attempt () ; \
+            retrying_metrics . record_retry (& retrying_context , :: retrying :: Duration :: ZERO) ; \
+        } }";
+
+        assert_eq!(result.to_string(), expected);
+    }
+
+    #[test]
+    fn test_add_retry_code_into_function_option() {
+        let config = RetryingConfig {
+            stop: Some(StopConfig {
+                attempts: Some(2),
+                duration: None,
+                when: None,
+            }),
+            wait: None,
+            retry: None,
+            envs_prefix: None,
+            envs_strict: false,
+            policy: None,
+            clone_args: false,
+            crate_path: None,
+            wrap_body: false,
+            metrics_label: None,
+            cancel: None,
+            abort: None,
+        };
+
+        let function = syn::parse_quote!(
+            fn test_function(in_param: &str) -> Option<i32> {
+                in_param.parse::<i32>().ok()
+            }
+        );
+
+        let result = add_retry_code_into_function(function, config).unwrap();
+
+        let expected = "\
+        fn test_function (in_param : & str) -> Option < i32 > { \
+            let mut retrying_context = :: retrying :: RetryingContext :: new () ; \
+            let retrying_metrics = { \
+                static RETRYING_METRICS : :: retrying :: metrics :: FunctionMetrics = :: retrying :: metrics :: FunctionMetrics :: new (\"test_function\" , :: core :: option :: Option :: None) ; \
+                & RETRYING_METRICS \
+            } ; \
+                        let retrying_stop = & :: retrying :: stop :: StopAttempts :: new (2u32) ; \
+            loop { { \
+                let retrying_result : Option < i32 > = { in_param . parse :: < i32 > () . ok () } ; \
+                match :: retrying :: Retryable :: outcome (& retrying_result) { \
+                    :: retrying :: Outcome :: Retry (_) if ! :: retrying :: stop :: Stop :: stop_execution (retrying_stop , & retrying_context) => { } , \
+                    retrying_outcome => { \
+                        retrying_metrics . record (& retrying_context , & retrying_outcome) ; \
+                        break retrying_result \
+                    } \
+                } \
+            } \
+            retrying_context . add_attempt () ; \
+            retrying_metrics . record_retry (& retrying_context , :: retrying :: Duration :: ZERO) ; \
+        } }";
+
+        assert_eq!(result.to_string(), expected);
+    }
+
+    #[test]
+    fn test_add_retry_code_into_function_cancel() {
+        let config = RetryingConfig {
+            stop: None,
+            wait: Some(WaitConfig::Fixed {
+                duration: Duration::from_secs(1),
+            }),
+            retry: None,
+            envs_prefix: None,
+            envs_strict: false,
+            policy: None,
+            clone_args: false,
+            crate_path: None,
+            wrap_body: true,
+            metrics_label: None,
+            cancel: Some(syn::parse_quote!(token.cancelled())),
+            abort: None,
+        };
+
+        let function = syn::parse_quote!(
+            async fn test_function(token: &Token) -> Result<i32, Error> {
+                call().await
+            }
+        );
+
+        let result = add_retry_code_into_function(function, config).unwrap();
+
+        let expected = "\
+        async fn test_function (token : & Token) -> Result < i32 , Error > { \
+            let mut retrying_context = :: retrying :: RetryingContext :: new () ; \
+            let retrying_metrics = { \
+                static RETRYING_METRICS : :: retrying :: metrics :: FunctionMetrics = :: retrying :: metrics :: FunctionMetrics :: new (\"test_function\" , :: core :: option :: Option :: None) ; \
+                & RETRYING_METRICS \
+            } ; \
+            let retrying_wait = & :: retrying :: wait :: WaitFixed :: new (:: retrying :: Duration :: new (1u64 , 0u32)) ; \
+            let mut retrying_cancel = :: retrying :: cancel :: Cancellation :: new (token . cancelled ()) ; \
+            loop { { \
+                let retrying_result : Result < i32 , Error > = match retrying_cancel . run (async { call () . await }) . await { \
+                    :: core :: option :: Option :: Some (retrying_result) => retrying_result , \
+                    :: core :: option :: Option :: None => { \
+                        retrying_metrics . record_cancelled (& retrying_context) ; \
+                        break :: retrying :: cancel :: Cancellable :: cancelled () \
+                    } \
+                } ; \
+                match :: retrying :: Retryable :: outcome (& retrying_result) { \
+                    :: retrying :: Outcome :: Retry (_) if true => { } , \
+                    retrying_outcome => { \
+                        retrying_metrics . record (& retrying_context , & retrying_outcome) ; \
+                        break retrying_result \
+                    } \
+                } \
+            } \
+            retrying_context . add_attempt () ; \
+            let retrying_wait_duration = :: retrying :: wait :: Wait :: wait_duration (retrying_wait , & retrying_context) ; \
+            retrying_metrics . record_retry (& retrying_context , retrying_wait_duration) ; \
+            retrying_context . add_wait (retrying_wait_duration) ; \
+            if retrying_cancel . run (:: retrying :: sleep_async (retrying_wait_duration)) . await . is_none () { \
+                retrying_metrics . record_cancelled (& retrying_context) ; \
+                break :: retrying :: cancel :: Cancellable :: cancelled () ; \
+            } \
+        } }";
+
+        assert_eq!(result.to_string(), expected);
+
+        let config = RetryingConfig {
+            cancel: Some(syn::parse_quote!(token.cancelled())),
+            ..RetryingConfig::from_token_stream(quote!()).unwrap()
+        };
+        let function = syn::parse_quote!(
+            fn test_function() -> Result<i32, Error> {
+                call()
+            }
+        );
+        assert!(add_retry_code_into_function(function, config).is_err());
+
+        let config = RetryingConfig {
+            cancel: Some(syn::parse_quote!(token.cancelled())),
+            ..RetryingConfig::from_token_stream(quote!(wrap_body = false)).unwrap()
+        };
+        let function = syn::parse_quote!(
+            async fn test_function() -> Result<i32, Error> {
+                call().await
+            }
+        );
+        assert!(add_retry_code_into_function(function, config).is_err());
+    }
+
+    #[test]
+    fn test_add_retry_code_into_function_abort() {
+        let config = RetryingConfig {
+            wait: Some(WaitConfig::Fixed {
+                duration: Duration::from_secs(1),
+            }),
+            abort: Some(syn::parse_quote!(crate::SHUTDOWN)),
+            ..RetryingConfig::from_token_stream(quote!(wrap_body = false)).unwrap()
+        };
+
+        let function = syn::parse_quote!(
+            fn test_function() -> Option<i32> {
+                call()
+            }
+        );
+
+        let result = add_retry_code_into_function(function, config).unwrap();
 
         let expected = "\
-        fn test_function < > (in_param : & str) -> Result < i32 , ParseIntError > { \
+        fn test_function () -> Option < i32 > { \
             let mut retrying_context = :: retrying :: RetryingContext :: new () ; \
-            loop { match { in_param . parse :: < i32 > () } { \
-                Ok (result) => return Ok (result) , \
-                Err (err) if true => { \
-                    retrying_context . add_attempt () ; \
-                } , \
-                Err (err) => break Err (err) \
+            let retrying_metrics = { \
+                static RETRYING_METRICS : :: retrying :: metrics :: FunctionMetrics = :: retrying :: metrics :: FunctionMetrics :: new (\"test_function\" , :: core :: option :: Option :: None) ; \
+                & RETRYING_METRICS \
+            } ; \
+            let retrying_wait = & :: retrying :: wait :: WaitFixed :: new (:: retrying :: Duration :: new (1u64 , 0u32)) ; \
+            loop { { \
+                let retrying_result : Option < i32 > = { call () } ; \
+                match :: retrying :: Retryable :: outcome (& retrying_result) { \
+                    :: retrying :: Outcome :: Retry (_) if true && ! :: retrying :: abort :: RetryAbort :: is_aborted (& crate :: SHUTDOWN) => { \
+                        retrying_context . add_attempt () ; \
+                        let retrying_wait_duration = :: retrying :: wait :: Wait :: wait_duration (retrying_wait , & retrying_context) ; \
+                        retrying_metrics . record_retry (& retrying_context , retrying_wait_duration) ; \
+                        retrying_context . add_wait (retrying_wait_duration) ; \
+                        if ! :: retrying :: abort :: RetryAbort :: sleep (& crate :: SHUTDOWN , retrying_wait_duration) { \
+                            retrying_metrics . record (& retrying_context , & :: retrying :: Retryable :: outcome (& retrying_result)) ; \
+                            break retrying_result \
+                        } \
+                    } , \
+                    retrying_outcome => { \
+                        retrying_metrics . record (& retrying_context , & retrying_outcome) ; \
+                        break retrying_result \
+                    } \
+                } \
             } \
         } }";
 
         assert_eq!(result.to_string(), expected);
+
+        let config = RetryingConfig {
+            abort: Some(syn::parse_quote!(SHUTDOWN)),
+            ..RetryingConfig::from_token_stream(quote!()).unwrap()
+        };
+        let function = syn::parse_quote!(
+            async fn test_function() -> Option<i32> {
+                call().await
+            }
+        );
+        assert!(add_retry_code_into_function(function, config).is_err());
+    }
+
+    #[test]
+    fn test_return_kind() {
+        assert_eq!(
+            return_kind(&syn::parse_quote!(Result<i32, ParseIntError>)).unwrap(),
+            ReturnKind::Result
+        );
+        assert_eq!(
+            return_kind(&syn::parse_quote!(std::io::Result<()>)).unwrap(),
+            ReturnKind::Result
+        );
+        assert_eq!(
+            return_kind(&syn::parse_quote!(::core::option::Option<i32>)).unwrap(),
+            ReturnKind::Option
+        );
+        assert_eq!(
+            return_kind(&syn::parse_quote!(impl Retryable<Ok = i32>)).unwrap(),
+            ReturnKind::Result
+        );
+
+        assert!(result_type(&syn::parse_quote!(), Span::call_site()).is_err());
+        assert!(return_kind(&syn::parse_quote!(())).is_err());
+        assert!(return_kind(&syn::parse_quote!((i32, i32))).is_err());
+        assert!(return_kind(&syn::parse_quote!(&'static str)).is_err());
+    }
+
+    #[test]
+    fn test_contains_impl_trait() {
+        assert!(contains_impl_trait(&syn::parse_quote!(impl Retryable)));
+        assert!(contains_impl_trait(&syn::parse_quote!(
+            Option<impl Display>
+        )));
+        assert!(contains_impl_trait(&syn::parse_quote!(
+            Result<(impl Display, i32), ()>
+        )));
+        assert!(!contains_impl_trait(
+            &syn::parse_quote!(Result<i32, ParseIntError>)
+        ));
+    }
+
+    #[test]
+    fn test_boxed_future() {
+        let return_type = syn::parse_quote!(-> ::core::pin::Pin<Box<dyn ::core::future::Future<Output = Result<i32, ()>> + Send + 'async_trait>>);
+        let block = syn::parse_quote!({ Box::pin(async move { Ok(1) }) });
+
+        let (pin, output, async_block) = boxed_future(&return_type, &block).unwrap().unwrap();
+        assert_eq!(quote!(#pin).to_string(), "Box :: pin");
+        assert_eq!(quote!(#output).to_string(), "Result < i32 , () >");
+        assert_eq!(quote!(#async_block).to_string(), "{ Ok (1) }");
+
+        let block = syn::parse_quote!({ Box::pin(async { Ok(1) }) });
+        assert!(boxed_future(&return_type, &block).is_err());
+
+        let return_type = syn::parse_quote!(-> Result<i32, ()>);
+        assert!(boxed_future(&return_type, &block).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_prepare_args() {
+        let (params, let_cloned_args) = prepare_args(
+            syn::parse_quote!(&mut self, mut name: String, id: &str),
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            quote!(#params).to_string(),
+            "& mut self , name : String , id : & str"
+        );
+        assert_eq!(
+            let_cloned_args.to_string(),
+            "let mut name = :: core :: clone :: Clone :: clone (& name) ;"
+        );
+
+        let (params, let_cloned_args) =
+            prepare_args(syn::parse_quote!(&self, mut name: String), false).unwrap();
+        assert_eq!(quote!(#params).to_string(), "& self , mut name : String");
+        assert!(let_cloned_args.is_empty());
+
+        assert!(prepare_args(syn::parse_quote!(self), false).is_err());
+        assert!(prepare_args(syn::parse_quote!(self: Box<Self>), false).is_err());
+        assert!(prepare_args(syn::parse_quote!(self: &Self), false).is_ok());
+        assert!(prepare_args(syn::parse_quote!((a, b): (i32, i32)), true).is_err());
     }
 }
@@ -1,12 +1,10 @@
 extern crate proc_macro;
 
-use crate::config::RetryingConfig;
 use proc_macro::TokenStream;
-use syn::ItemFn;
+use retrying_config::{split_policy_path, PolicyDefinition, RetryingConfig};
+use syn::{ItemFn, TraitItemFn};
 
 mod code_gen;
-mod config;
-mod errors;
 
 /// macros that allows add retrying functionality to rust functions
 /// # Examples
@@ -19,9 +17,62 @@ mod errors;
 /// ```
 #[proc_macro_attribute]
 pub fn retry(args: TokenStream, item: TokenStream) -> TokenStream {
-    let config = RetryingConfig::from_token_stream(args.into()).unwrap();
+    let function: ItemFn = match syn::parse(item.clone()) {
+        Ok(function) => function,
+        Err(err) => {
+            let err = match syn::parse::<TraitItemFn>(item) {
+                Ok(TraitItemFn {
+                    default: None,
+                    semi_token,
+                    ..
+                }) => syn::Error::new_spanned(
+                    semi_token,
+                    "`retry` macros can be applied only to trait methods with default implementation",
+                ),
+                _ => err,
+            };
+            return err.to_compile_error().into();
+        }
+    };
 
-    let function: ItemFn = syn::parse(item).unwrap();
+    let result = split_policy_path(args.into()).and_then(|(policy, args)| match policy {
+        Some(policy) => Ok(crate::code_gen::add_policy_into_function(
+            policy,
+            args,
+            item.clone().into(),
+        )),
+        None => RetryingConfig::from_token_stream(args)
+            .and_then(|config| crate::code_gen::add_retry_code_into_function(function, config)),
+    });
 
-    crate::code_gen::add_retry_code_into_function(function, config).into()
+    match result {
+        Ok(result) => result.into(),
+        Err(err) => {
+            // original function is kept, so the only reported error is the configuration one
+            let mut result: TokenStream = err.to_compile_error().into();
+            result.extend(item);
+            result
+        }
+    }
+}
+
+/// macros that defines named retry policy which can be used by several functions with `retry` macros.
+/// `pub` policies can be used by other crates, their hidden macros are exported from the crate root,
+/// so names of `pub` policies should be unique within the crate.
+/// # Examples
+///
+/// ```ignore
+/// retrying::policy!(pub(crate) DB_POLICY, stop=attempts(5), wait=fixed("100ms"));
+///
+/// #[retry(policy=DB_POLICY, stop=attempts(2))]
+/// fn my_function() -> Result<(),Error> {
+/// .....
+///}
+/// ```
+#[proc_macro]
+pub fn policy(input: TokenStream) -> TokenStream {
+    match PolicyDefinition::from_token_stream(input.into()) {
+        Ok(definition) => crate::code_gen::define_policy(definition).into(),
+        Err(err) => err.to_compile_error().into(),
+    }
 }
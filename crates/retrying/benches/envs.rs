@@ -0,0 +1,51 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use retrying::envs::{stop_from_env, wait_from_env, EnvCache, EnvErrors};
+use retrying::stop::StopStrategy;
+use retrying::wait::WaitStrategy;
+use retrying::{retry, Duration, Policy};
+
+const STOP: StopStrategy = StopStrategy {
+    attempts: Some(3),
+    duration: Some(Duration::from_secs(10)),
+};
+
+const WAIT: Option<WaitStrategy> = Some(WaitStrategy::Exponential {
+    multiplier: Duration::from_secs(1),
+    min: Duration::ZERO,
+    max: Duration::from_secs(10),
+    exp_base: 2,
+});
+
+fn load_policy() -> Policy {
+    Policy::new(
+        stop_from_env("BENCH", STOP, EnvErrors::Warn),
+        wait_from_env("BENCH", WAIT, EnvErrors::Warn),
+    )
+}
+
+#[retry(stop=(attempts(3)|duration(10)),wait=exponential(max=10),envs_prefix="BENCH")]
+fn succeed_with_envs(value: u32) -> Result<u32, ()> {
+    Ok(value)
+}
+
+fn envs_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("envs");
+
+    group.bench_function("read_env_on_every_call", |b| {
+        b.iter(|| black_box(load_policy()))
+    });
+
+    let cache = EnvCache::new();
+    group.bench_function("cached_env", |b| {
+        b.iter(|| black_box(cache.get_or_load(load_policy)))
+    });
+
+    group.bench_function("retry_macros_with_envs_prefix", |b| {
+        b.iter(|| succeed_with_envs(black_box(1)))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, envs_benchmark);
+criterion_main!(benches);
@@ -14,9 +14,21 @@ fn main() {
     try_retry_attempts_exponential("try_retry_attempts_exponential");
 
     std::env::set_var("MY_METHOD__RETRYING__STOP__ATTEMPTS", "3");
-    std::env::set_var("MY_METHOD__RETRYING__WAIT__FIXED", "1.01");
+    std::env::set_var("MY_METHOD__RETRYING__WAIT__FIXED", "1s10ms");
     try_retry_attempts_fixed_env("try_retry_attempts_fixed_env");
 
+    try_retry_shared_policy("try_retry_shared_policy");
+    retrying::PolicyRegistry::global().insert(
+        "shared",
+        retrying::Policy::new(
+            Box::new(retrying::stop::StopAttempts::new(2)),
+            Box::new(retrying::wait::WaitFixed::new(
+                retrying::Duration::from_millis(100),
+            )),
+        ),
+    );
+    try_retry_shared_policy("try_retry_shared_policy_replaced");
+
     try_retry_if_errors("try_retry_if_errors");
 
     try_retry_if_not_errors("try_retry_if_not_errors");
@@ -30,7 +42,7 @@ fn try_retry_attempts(in_param: &str) -> Result<i32, ParseIntError> {
     in_param.parse::<i32>()
 }
 
-#[retry(stop=duration(0.2))]
+#[retry(stop=duration("200ms"))]
 fn try_retry_duration(in_param: &str) -> Result<i32, ParseIntError> {
     println!("{}", in_param);
     in_param.parse::<i32>()
@@ -48,7 +60,7 @@ fn try_retry_attempts_random(in_param: &str) -> Result<i32, ParseIntError> {
     in_param.parse::<i32>()
 }
 
-#[retry(stop=attempts(4),wait=exponential(multiplier=0.555, min=1,max=10))]
+#[retry(stop=attempts(4),wait=exponential(multiplier="555ms", min=1,max="10s"))]
 fn try_retry_attempts_exponential(in_param: &str) -> Result<i32, ParseIntError> {
     println!("{}", in_param);
     in_param.parse::<i32>()
@@ -60,6 +72,12 @@ fn try_retry_attempts_fixed_env(in_param: &str) -> Result<i32, ParseIntError> {
     in_param.parse::<i32>()
 }
 
+#[retry(stop=attempts(3),wait=fixed(0.5),policy="shared")]
+fn try_retry_shared_policy(in_param: &str) -> Result<i32, ParseIntError> {
+    println!("{}", in_param);
+    in_param.parse::<i32>()
+}
+
 #[retry(stop=attempts(3),retry=if_errors(std::num::ParseIntError, ::std::num::ParseIntError))]
 fn try_retry_if_errors(in_param: &str) -> Result<i32, ParseIntError> {
     println!("{}", in_param);
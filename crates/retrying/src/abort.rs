@@ -0,0 +1,166 @@
+//! Interruption of synchronous retries.
+//!
+//! `std::thread::sleep` can't be woken up, so functions with `retry` macros and `abort=<path to RetryAbort>`
+//! configuration option wait between attempts using [`Sleeper`] of shared [`RetryAbort`] handle. When the handle is
+//! aborted (for example, on shutdown), sleeping retry loops wake up immediately and return the last result.
+
+use crate::Duration;
+use std::sync::{Condvar, Mutex};
+use std::time::Instant;
+
+/// Sleep based on condition variable that can be interrupted from other thread.
+pub struct Sleeper {
+    interrupted: Mutex<bool>,
+    condvar: Condvar,
+}
+
+impl Sleeper {
+    pub const fn new() -> Sleeper {
+        Sleeper {
+            interrupted: Mutex::new(false),
+            condvar: Condvar::new(),
+        }
+    }
+
+    /// sleeps for `duration` or until the sleeper is interrupted. Returns `false` if the sleeper is interrupted.
+    pub fn sleep(&self, duration: Duration) -> bool {
+        let deadline = Instant::now().checked_add(duration);
+        let mut interrupted = self.interrupted.lock().unwrap_or_else(|e| e.into_inner());
+        while !*interrupted {
+            let timeout = match deadline {
+                Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                    Some(timeout) if !timeout.is_zero() => timeout,
+                    _ => return true,
+                },
+                // duration is too big to be represented as `Instant`, so the sleeper sleeps until interruption
+                None => Duration::MAX,
+            };
+            interrupted = self
+                .condvar
+                .wait_timeout(interrupted, timeout)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+        false
+    }
+
+    /// wakes up all sleeping threads, following sleeps return immediately until [`Sleeper::reset`].
+    pub fn interrupt(&self) {
+        *self.interrupted.lock().unwrap_or_else(|e| e.into_inner()) = true;
+        self.condvar.notify_all();
+    }
+
+    pub fn is_interrupted(&self) -> bool {
+        *self.interrupted.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn reset(&self) {
+        *self.interrupted.lock().unwrap_or_else(|e| e.into_inner()) = false;
+    }
+}
+
+impl Default for Sleeper {
+    fn default() -> Self {
+        Sleeper::new()
+    }
+}
+
+/// Shared handle that aborts synchronous retries of functions with `abort` configuration option
+/// (or [`Policy::retry_with_abort`](crate::Policy::retry_with_abort)).
+///
+/// ```ignore
+/// static SHUTDOWN: retrying::abort::RetryAbort = retrying::abort::RetryAbort::new();
+///
+/// #[retrying::retry(stop=attempts(5),wait=fixed(60),abort=SHUTDOWN)]
+/// fn fetch() -> Result<String, Error> {
+///     ...
+/// }
+///
+/// // on shutdown
+/// SHUTDOWN.abort();
+/// ```
+pub struct RetryAbort {
+    sleeper: Sleeper,
+}
+
+impl RetryAbort {
+    pub const fn new() -> RetryAbort {
+        RetryAbort {
+            sleeper: Sleeper::new(),
+        }
+    }
+
+    /// aborts running and following retries until [`RetryAbort::reset`].
+    pub fn abort(&self) {
+        self.sleeper.interrupt();
+    }
+
+    pub fn is_aborted(&self) -> bool {
+        self.sleeper.is_interrupted()
+    }
+
+    pub fn reset(&self) {
+        self.sleeper.reset();
+    }
+
+    /// sleeps for `duration` or until retries are aborted. Returns `false` if retries are aborted.
+    pub fn sleep(&self, duration: Duration) -> bool {
+        self.sleeper.sleep(duration)
+    }
+}
+
+impl Default for RetryAbort {
+    fn default() -> Self {
+        RetryAbort::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_sleeper_sleep() {
+        let sleeper = Sleeper::new();
+        let started = Instant::now();
+        assert!(sleeper.sleep(Duration::from_millis(20)));
+        assert!(started.elapsed() >= Duration::from_millis(20));
+        assert!(sleeper.sleep(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_sleeper_interrupt() {
+        let sleeper = Arc::new(Sleeper::new());
+        let started = Instant::now();
+        let thread = {
+            let sleeper = sleeper.clone();
+            std::thread::spawn(move || sleeper.sleep(Duration::from_secs(60)))
+        };
+        std::thread::sleep(Duration::from_millis(20));
+        sleeper.interrupt();
+
+        assert!(!thread.join().unwrap());
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert!(sleeper.is_interrupted());
+        assert!(!sleeper.sleep(Duration::from_secs(60)));
+
+        sleeper.reset();
+        assert!(!sleeper.is_interrupted());
+        assert!(sleeper.sleep(Duration::from_millis(1)));
+    }
+
+    #[test]
+    fn test_retry_abort() {
+        static ABORT: RetryAbort = RetryAbort::new();
+        assert!(!ABORT.is_aborted());
+        assert!(ABORT.sleep(Duration::from_millis(1)));
+
+        ABORT.abort();
+        assert!(ABORT.is_aborted());
+        assert!(!ABORT.sleep(Duration::MAX));
+
+        ABORT.reset();
+        assert!(!ABORT.is_aborted());
+    }
+}
@@ -0,0 +1,114 @@
+//! Cancellation of asynchronous retries.
+//!
+//! Functions with `retry` macros and `cancel=<future>` configuration option race every attempt and every wait between
+//! attempts against the future and return [`Cancellable::cancelled`] result as soon as the future completes.
+//! For example, `cancel=token.cancelled()` for tokio's `CancellationToken` or `cancel=shutdown_rx.changed()` for watch channel.
+
+use std::fmt;
+use std::future::{Future, IntoFuture};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Error of retrying that was stopped by cancellation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Retrying was cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Result type that can be returned by function with `cancel` configuration option when retrying is cancelled.
+///
+/// Implemented for `Result<T, E>` where `E: From<Cancelled>`, so cancellation is distinguishable from errors of attempts.
+pub trait Cancellable {
+    fn cancelled() -> Self;
+}
+
+impl<T, E: From<Cancelled>> Cancellable for Result<T, E> {
+    fn cancelled() -> Self {
+        Err(E::from(Cancelled))
+    }
+}
+
+/// Cancellation signal of retry loop.
+/// This struct is a part of developer API and should not be used directly.
+pub struct Cancellation<F> {
+    signal: Pin<Box<F>>,
+    cancelled: bool,
+}
+
+impl<F: Future> Cancellation<F> {
+    pub fn new<S: IntoFuture<IntoFuture = F>>(signal: S) -> Cancellation<F> {
+        Cancellation {
+            signal: Box::pin(signal.into_future()),
+            cancelled: false,
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    /// runs `future` until it completes or retrying is cancelled. Returns `None` if retrying is cancelled.
+    pub async fn run<T, A: Future<Output = T>>(&mut self, future: A) -> Option<T> {
+        let mut future = Box::pin(future);
+        std::future::poll_fn(|cx| {
+            if self.poll_cancelled(cx).is_ready() {
+                return Poll::Ready(None);
+            }
+            future.as_mut().poll(cx).map(Some)
+        })
+        .await
+    }
+
+    fn poll_cancelled(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        if !self.cancelled && self.signal.as_mut().poll(cx).is_ready() {
+            self.cancelled = true;
+        }
+        if self.cancelled {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::future::{pending, ready};
+
+    #[derive(Debug, PartialEq)]
+    enum Error {
+        Cancelled,
+    }
+
+    impl From<Cancelled> for Error {
+        fn from(_: Cancelled) -> Self {
+            Error::Cancelled
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancellation_run() {
+        let mut cancellation = Cancellation::new(pending::<()>());
+        assert_eq!(cancellation.run(ready(1)).await, Some(1));
+        assert!(!cancellation.is_cancelled());
+
+        let mut cancellation = Cancellation::new(ready(()));
+        assert_eq!(cancellation.run(ready(1)).await, None);
+        assert!(cancellation.is_cancelled());
+        assert_eq!(cancellation.run(pending::<i32>()).await, None);
+    }
+
+    #[test]
+    fn test_cancellable() {
+        assert_eq!(<Result<i32, Error>>::cancelled(), Err(Error::Cancelled));
+        assert_eq!(<Result<i32, Cancelled>>::cancelled(), Err(Cancelled));
+        assert_eq!(Cancelled.to_string(), "Retrying was cancelled");
+    }
+}
@@ -1,5 +1,14 @@
+use crate::stop::{Stop, StopStrategy};
+use crate::wait::{Wait, WaitStrategy};
+use crate::{get_env_case_insensitive, parse_duration, Duration, Policy, RetryingError};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+
+pub const RETRYING_STOP: &str = "RETRYING__STOP";
 pub const RETRYING_STOP_ATTEMPTS: &str = "RETRYING__STOP__ATTEMPTS";
 pub const RETRYING_STOP_DURATION: &str = "RETRYING__STOP__DURATION";
+pub const RETRYING_WAIT: &str = "RETRYING__WAIT";
 pub const RETRYING_WAIT_FIXED: &str = "RETRYING__WAIT__FIXED";
 pub const RETRYING_WAIT_RANDOM_MIN: &str = "RETRYING__WAIT__RANDOM__MIN";
 pub const RETRYING_WAIT_RANDOM_MAX: &str = "RETRYING__WAIT__RANDOM__MAX";
@@ -7,3 +16,538 @@ pub const RETRYING_WAIT_EXPONENTIAL_MULTIPLIER: &str = "RETRYING__WAIT__EXPONENT
 pub const RETRYING_WAIT_EXPONENTIAL_MIN: &str = "RETRYING__WAIT__EXPONENTIAL__MIN";
 pub const RETRYING_WAIT_EXPONENTIAL_MAX: &str = "RETRYING__WAIT__EXPONENTIAL__MAX";
 pub const RETRYING_WAIT_EXPONENTIAL_EXP_BASE: &str = "RETRYING__WAIT__EXPONENTIAL__EXP_BASE";
+
+const STOP_ATTEMPTS: &str = "attempts";
+const STOP_DURATION: &str = "duration";
+const STOP_NEVER: &str = "never";
+
+static ENVS_GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+/// forces all functions with `envs_prefix` configuration option to read OS environment variables again on their next call.
+/// OS environment variables are read only once per function (on the first call) and cached, so changes of environment
+/// variables after the first call are ignored until this function is called.
+pub fn reload_env() {
+    ENVS_GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Lazily loaded policy built from OS environment variables. `retry` macros creates one static cache per function with
+/// `envs_prefix` configuration option, so OS environment variables are read once instead of on every call.
+/// Cache is invalidated by [`reload_env`].
+/// This struct is a part of developer API and should not be used directly.
+pub struct EnvCache {
+    policy: RwLock<Option<(usize, Arc<Policy>)>>,
+}
+
+impl EnvCache {
+    pub const fn new() -> EnvCache {
+        EnvCache {
+            policy: RwLock::new(None),
+        }
+    }
+
+    /// returns cached policy or builds it with `load` if policy is not loaded yet or [`reload_env`] was called after loading.
+    pub fn get_or_load<F: FnOnce() -> Policy>(&self, load: F) -> Arc<Policy> {
+        let generation = ENVS_GENERATION.load(Ordering::SeqCst);
+
+        if let Some((loaded, policy)) = &*self.policy.read().unwrap_or_else(|e| e.into_inner()) {
+            if *loaded == generation {
+                return policy.clone();
+            }
+        }
+
+        let mut cached = self.policy.write().unwrap_or_else(|e| e.into_inner());
+        match &*cached {
+            Some((loaded, policy)) if *loaded == generation => policy.clone(),
+            _ => {
+                let policy = Arc::new(load());
+                *cached = Some((generation, policy.clone()));
+                policy
+            }
+        }
+    }
+}
+
+impl Default for EnvCache {
+    fn default() -> Self {
+        EnvCache::new()
+    }
+}
+
+/// How OS environment variables with wrong values (malformed, out of range or duplicated in different case) are handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnvErrors {
+    /// silently use values from code.
+    Ignore,
+    /// log error (using `log` crate if `log` feature is enabled or stderr otherwise) and use values from code.
+    Warn,
+    /// panic on the first call of the function that reads wrong OS environment variables.
+    Panic,
+}
+
+static ENV_ERRORS: AtomicU8 = AtomicU8::new(EnvErrors::Warn as u8);
+
+/// sets global handling of wrong OS environment variables. Default is [`EnvErrors::Warn`].
+/// Functions with `envs_strict=true` configuration option always panic.
+pub fn set_env_errors(errors: EnvErrors) {
+    ENV_ERRORS.store(errors as u8, Ordering::SeqCst);
+}
+
+/// returns global handling of wrong OS environment variables.
+pub fn env_errors() -> EnvErrors {
+    match ENV_ERRORS.load(Ordering::SeqCst) {
+        v if v == EnvErrors::Ignore as u8 => EnvErrors::Ignore,
+        v if v == EnvErrors::Panic as u8 => EnvErrors::Panic,
+        _ => EnvErrors::Warn,
+    }
+}
+
+/// runs `f` with global handling of wrong OS environment variables set to `errors` and restores the previous value.
+/// Tests that depend on the global handling are serialized, so parallel tests don't see each other's values.
+#[cfg(test)]
+pub(crate) fn with_env_errors<T, F>(errors: EnvErrors, f: F) -> std::thread::Result<T>
+where
+    F: FnOnce() -> T + std::panic::UnwindSafe,
+{
+    static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    let _guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let previous = env_errors();
+    set_env_errors(errors);
+    let result = std::panic::catch_unwind(f);
+    set_env_errors(previous);
+    result
+}
+
+/// build stop strategy in runtime using OS environment variables with `prefix` and stop strategy from macros configuration.
+/// `{PREFIX}__RETRYING__STOP` (`attempts`, `duration`, `attempts|duration` or `never`) overrides the strategy itself
+/// and `{PREFIX}__RETRYING__STOP__(ATTEMPTS|DURATION)` override its values.
+/// Wrong OS environment variables are handled according to `errors`. If they are not leading to panic then wrong values
+/// are ignored, and strategy from code is used if the resulting strategy requires a value that is set neither in
+/// environment nor in code or has values out of range.
+/// This method is a part of developer API and should not be used directly (it is public because `retry` macros uses it together with `envs_prefix` configuration option).
+pub fn stop_from_env(
+    prefix: &str,
+    code: StopStrategy,
+    errors: EnvErrors,
+) -> Box<dyn Stop + Send + Sync> {
+    let mut reader = EnvReader::new(prefix);
+    let strategy = read_stop(&mut reader, code);
+    reader.report(errors);
+    strategy.build()
+}
+
+fn read_stop(reader: &mut EnvReader<'_>, code: StopStrategy) -> StopStrategy {
+    let prefix = reader.prefix;
+    let (use_attempts, use_duration) = match reader.value::<String>(RETRYING_STOP) {
+        Some(strategy) => match parse_stop_strategy(&strategy) {
+            Some(flags) => flags,
+            None => {
+                reader.error(format!(
+                    "Unknown stop strategy '{}' in OS env variable '{}__{}'. Possible values are `{}`, `{}`, `{}|{}` and `{}`.",
+                    strategy, prefix, RETRYING_STOP, STOP_ATTEMPTS, STOP_DURATION, STOP_ATTEMPTS, STOP_DURATION, STOP_NEVER
+                ));
+                (code.attempts.is_some(), code.duration.is_some())
+            }
+        },
+        None => (code.attempts.is_some(), code.duration.is_some()),
+    };
+
+    let attempts = reader.value(RETRYING_STOP_ATTEMPTS).or(code.attempts);
+    let duration = reader.duration(RETRYING_STOP_DURATION).or(code.duration);
+
+    let strategy = if (use_attempts && attempts.is_none()) || (use_duration && duration.is_none()) {
+        reader.error(format!(
+            "Stop strategy from OS env variable '{}__{}' requires value in '{}__{}' or '{}__{}'. Configuration from code is used.",
+            prefix, RETRYING_STOP, prefix, RETRYING_STOP_ATTEMPTS, prefix, RETRYING_STOP_DURATION
+        ));
+        code
+    } else {
+        StopStrategy {
+            attempts: attempts.filter(|_| use_attempts),
+            duration: duration.filter(|_| use_duration),
+        }
+    };
+
+    match strategy.validate() {
+        Ok(()) => strategy,
+        Err(err) => {
+            reader.error(format!(
+                "Stop strategy from OS env variables with prefix '{}' is wrong. Error: {}. Configuration from code is used.",
+                prefix, err.msg
+            ));
+            code
+        }
+    }
+}
+
+/// build wait strategy in runtime using OS environment variables with `prefix` and wait strategy from macros configuration.
+/// `{PREFIX}__RETRYING__WAIT` (`fixed`, `random` or `exponential`) overrides the strategy itself and
+/// `{PREFIX}__RETRYING__WAIT__*` variables override its values. If the strategy from environment differs from the strategy
+/// in code then its default values are used for values that are not set in environment.
+/// If wait strategy is configured neither in code nor in environment then method returns zero fixed wait.
+/// Wrong OS environment variables are handled according to `errors`. If they are not leading to panic then wrong values
+/// are ignored, and strategy from code is used if the resulting strategy has values out of range.
+/// This method is a part of developer API and should not be used directly (it is public because `retry` macros uses it together with `envs_prefix` configuration option).
+pub fn wait_from_env(
+    prefix: &str,
+    code: Option<WaitStrategy>,
+    errors: EnvErrors,
+) -> Box<dyn Wait + Send + Sync> {
+    let mut reader = EnvReader::new(prefix);
+    let strategy = read_wait(&mut reader, code);
+    reader.report(errors);
+    strategy.build()
+}
+
+fn read_wait(reader: &mut EnvReader<'_>, code: Option<WaitStrategy>) -> WaitStrategy {
+    let prefix = reader.prefix;
+    let strategy = match reader.value::<String>(RETRYING_WAIT) {
+        Some(name) => match WaitStrategy::from_name(&name) {
+            Some(default) => code
+                .filter(|c| c.name() == default.name())
+                .or(Some(default)),
+            None => {
+                reader.error(format!(
+                    "Unknown wait strategy '{}' in OS env variable '{}__{}'. Possible values are `{}`, `{}` and `{}`.",
+                    name, prefix, RETRYING_WAIT, WaitStrategy::FIXED, WaitStrategy::RANDOM, WaitStrategy::EXPONENTIAL
+                ));
+                code
+            }
+        },
+        None => code,
+    };
+
+    let strategy = match strategy {
+        Some(WaitStrategy::Fixed { duration }) => WaitStrategy::Fixed {
+            duration: reader.duration(RETRYING_WAIT_FIXED).unwrap_or(duration),
+        },
+        Some(WaitStrategy::Random { min, max }) => WaitStrategy::Random {
+            min: reader.duration(RETRYING_WAIT_RANDOM_MIN).unwrap_or(min),
+            max: reader.duration(RETRYING_WAIT_RANDOM_MAX).unwrap_or(max),
+        },
+        Some(WaitStrategy::Exponential {
+            multiplier,
+            min,
+            max,
+            exp_base,
+        }) => WaitStrategy::Exponential {
+            multiplier: reader
+                .duration(RETRYING_WAIT_EXPONENTIAL_MULTIPLIER)
+                .unwrap_or(multiplier),
+            min: reader
+                .duration(RETRYING_WAIT_EXPONENTIAL_MIN)
+                .unwrap_or(min),
+            max: reader
+                .duration(RETRYING_WAIT_EXPONENTIAL_MAX)
+                .unwrap_or(max),
+            exp_base: reader
+                .value(RETRYING_WAIT_EXPONENTIAL_EXP_BASE)
+                .unwrap_or(exp_base),
+        },
+        None => WaitStrategy::Fixed {
+            duration: Duration::ZERO,
+        },
+    };
+
+    match strategy.validate() {
+        Ok(()) => strategy,
+        Err(err) => {
+            reader.error(format!(
+                "Wait strategy from OS env variables with prefix '{}' is wrong. Error: {}. Configuration from code is used.",
+                prefix, err.msg
+            ));
+            code.unwrap_or(WaitStrategy::Fixed {
+                duration: Duration::ZERO,
+            })
+        }
+    }
+}
+
+/// Stop and wait strategies after applying OS environment variables (see [`check_env`]).
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvOverrides {
+    pub stop: StopStrategy,
+    pub wait: WaitStrategy,
+    /// errors of wrong OS environment variables, which are ignored in `stop` and `wait`.
+    pub errors: Vec<String>,
+}
+
+/// applies OS environment variables with `prefix` to stop and wait strategies like functions with `envs_prefix`
+/// configuration option and returns the resulting strategies together with all errors instead of reporting them.
+pub fn check_env(prefix: &str, stop: StopStrategy, wait: Option<WaitStrategy>) -> EnvOverrides {
+    let mut reader = EnvReader::new(prefix);
+    let stop = read_stop(&mut reader, stop);
+    let wait = read_wait(&mut reader, wait);
+    EnvOverrides {
+        stop,
+        wait,
+        errors: reader.errors,
+    }
+}
+
+/// Reads OS environment variables with the same prefix and collects errors.
+pub(crate) struct EnvReader<'a> {
+    prefix: &'a str,
+    errors: Vec<String>,
+}
+
+impl<'a> EnvReader<'a> {
+    pub(crate) fn new(prefix: &'a str) -> EnvReader<'a> {
+        EnvReader {
+            prefix,
+            errors: Vec::new(),
+        }
+    }
+
+    /// returns the parsed value of OS environment variable if it exists and has correct format.
+    pub(crate) fn value<T: FromStr>(&mut self, name: &str) -> Option<T> {
+        self.parsed_value(name, |v| v.parse::<T>().map_err(|_| ()))
+    }
+
+    /// returns duration from OS environment variable (number of seconds or value with units like `500ms`)
+    /// if it exists and has correct format.
+    pub(crate) fn duration(&mut self, name: &str) -> Option<Duration> {
+        self.parsed_value(name, |v| parse_duration(v).map_err(|_| ()))
+    }
+
+    fn parsed_value<T, F: FnOnce(&str) -> Result<T, ()>>(
+        &mut self,
+        name: &str,
+        parse: F,
+    ) -> Option<T> {
+        let os_variable = format!("{}__{}", self.prefix, name);
+
+        match get_env_case_insensitive(&os_variable) {
+            Ok(Some(v)) => match parse(&v) {
+                Ok(parsed) => Some(parsed),
+                Err(_) => {
+                    self.error(format!(
+                        "Failed to parse OS env variable '{}' with value '{}'.",
+                        os_variable, v
+                    ));
+                    None
+                }
+            },
+            Ok(None) => None,
+            Err(RetryingError { msg }) => {
+                self.error(format!(
+                    "Failed to get OS env variable '{}'. Error: {}",
+                    os_variable, msg
+                ));
+                None
+            }
+        }
+    }
+
+    pub(crate) fn error(&mut self, msg: String) {
+        self.errors.push(msg);
+    }
+
+    /// handles collected errors according to `errors`.
+    pub(crate) fn report(self, errors: EnvErrors) {
+        if self.errors.is_empty() {
+            return;
+        }
+
+        match errors {
+            EnvErrors::Ignore => (),
+            EnvErrors::Warn => {
+                for msg in &self.errors {
+                    warn(msg);
+                }
+            }
+            EnvErrors::Panic => panic!(
+                "Wrong retrying configuration in OS env variables with prefix '{}'. {}",
+                self.prefix,
+                self.errors.join(" ")
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "log")]
+pub(crate) fn warn(msg: &str) {
+    log::warn!("{}", msg);
+}
+
+#[cfg(not(feature = "log"))]
+pub(crate) fn warn(msg: &str) {
+    eprintln!("{}", msg);
+}
+
+fn parse_stop_strategy(strategy: &str) -> Option<(bool, bool)> {
+    let mut use_attempts = false;
+    let mut use_duration = false;
+
+    for option in strategy.split('|') {
+        match option.trim().to_lowercase().as_str() {
+            STOP_ATTEMPTS => use_attempts = true,
+            STOP_DURATION => use_duration = true,
+            STOP_NEVER => (),
+            _ => return None,
+        }
+    }
+    Some((use_attempts, use_duration))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RetryingContext;
+
+    #[test]
+    fn test_env_cache() {
+        let cache = EnvCache::new();
+
+        let first = cache.get_or_load(Policy::default);
+        let second = cache.get_or_load(|| unreachable!());
+        assert!(Arc::ptr_eq(&first, &second));
+
+        reload_env();
+        let reloaded = cache.get_or_load(Policy::default);
+        assert!(!Arc::ptr_eq(&first, &reloaded));
+    }
+
+    #[test]
+    fn test_parse_stop_strategy() {
+        assert_eq!(parse_stop_strategy("attempts"), Some((true, false)));
+        assert_eq!(parse_stop_strategy("Duration"), Some((false, true)));
+        assert_eq!(
+            parse_stop_strategy("attempts | duration"),
+            Some((true, true))
+        );
+        assert_eq!(parse_stop_strategy("never"), Some((false, false)));
+        assert_eq!(parse_stop_strategy("attempts|delay"), None);
+    }
+
+    #[test]
+    fn test_stop_from_env() {
+        let code = StopStrategy {
+            attempts: None,
+            duration: Some(Duration::from_secs(3600)),
+        };
+        let mut ctx = RetryingContext::new();
+        ctx.add_attempt();
+
+        let stop = stop_from_env("STOP_FROM_ENV", code, EnvErrors::Warn);
+        assert!(!stop.stop_execution(&ctx));
+
+        std::env::set_var("STOP_FROM_ENV__RETRYING__STOP", "attempts");
+        let stop = stop_from_env("STOP_FROM_ENV", code, EnvErrors::Warn);
+        assert!(!stop.stop_execution(&ctx));
+
+        std::env::set_var("STOP_FROM_ENV__RETRYING__STOP__ATTEMPTS", "2");
+        let stop = stop_from_env("STOP_FROM_ENV", code, EnvErrors::Warn);
+        assert!(stop.stop_execution(&ctx));
+
+        std::env::remove_var("STOP_FROM_ENV__RETRYING__STOP");
+        std::env::remove_var("STOP_FROM_ENV__RETRYING__STOP__ATTEMPTS");
+    }
+
+    #[test]
+    fn test_wait_from_env() {
+        let code = Some(WaitStrategy::Fixed {
+            duration: Duration::from_millis(500),
+        });
+        let mut ctx = RetryingContext::new();
+        ctx.add_attempt();
+
+        let wait = wait_from_env("WAIT_FROM_ENV", code, EnvErrors::Warn);
+        assert_eq!(wait.wait_duration(&ctx), Duration::from_millis(500));
+
+        std::env::set_var("WAIT_FROM_ENV__RETRYING__WAIT", "exponential");
+        std::env::set_var("WAIT_FROM_ENV__RETRYING__WAIT__EXPONENTIAL__MIN", "1s");
+        let wait = wait_from_env("WAIT_FROM_ENV", code, EnvErrors::Warn);
+        assert_eq!(wait.wait_duration(&ctx), Duration::from_secs(3));
+
+        let wait = wait_from_env("WAIT_FROM_ENV", None, EnvErrors::Warn);
+        assert_eq!(wait.wait_duration(&ctx), Duration::from_secs(3));
+
+        std::env::remove_var("WAIT_FROM_ENV__RETRYING__WAIT");
+        std::env::remove_var("WAIT_FROM_ENV__RETRYING__WAIT__EXPONENTIAL__MIN");
+
+        let wait = wait_from_env("WAIT_FROM_ENV", None, EnvErrors::Warn);
+        assert_eq!(wait.wait_duration(&ctx), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_check_env() {
+        let stop = StopStrategy {
+            attempts: Some(3),
+            duration: None,
+        };
+        let wait = WaitStrategy::Fixed {
+            duration: Duration::from_secs(1),
+        };
+
+        let overrides = check_env("CHECK_ENV", stop, Some(wait));
+        assert_eq!(overrides.stop, stop);
+        assert_eq!(overrides.wait, wait);
+        assert!(overrides.errors.is_empty());
+
+        std::env::set_var("CHECK_ENV__RETRYING__STOP__ATTEMPTS", "5");
+        std::env::set_var("CHECK_ENV__RETRYING__WAIT__FIXED", "soon");
+        let overrides = check_env("CHECK_ENV", stop, Some(wait));
+        assert_eq!(overrides.stop.attempts, Some(5));
+        assert_eq!(overrides.wait, wait);
+        assert_eq!(
+            overrides.errors,
+            vec![String::from(
+                "Failed to parse OS env variable 'CHECK_ENV__RETRYING__WAIT__FIXED' with value 'soon'."
+            )]
+        );
+
+        std::env::remove_var("CHECK_ENV__RETRYING__STOP__ATTEMPTS");
+        std::env::remove_var("CHECK_ENV__RETRYING__WAIT__FIXED");
+    }
+
+    #[test]
+    fn test_wait_from_env_out_of_range() {
+        let code = Some(WaitStrategy::Random {
+            min: Duration::from_secs(1),
+            max: Duration::from_secs(2),
+        });
+        let ctx = RetryingContext::new();
+
+        std::env::set_var("WAIT_OUT_OF_RANGE__RETRYING__WAIT__RANDOM__MIN", "5");
+        let wait = wait_from_env("WAIT_OUT_OF_RANGE", code, EnvErrors::Ignore);
+        assert!(
+            (Duration::from_secs(1)..=Duration::from_secs(2)).contains(&wait.wait_duration(&ctx))
+        );
+
+        std::env::remove_var("WAIT_OUT_OF_RANGE__RETRYING__WAIT__RANDOM__MIN");
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Failed to parse OS env variable 'STOP_STRICT__RETRYING__STOP__ATTEMPTS'"
+    )]
+    fn test_stop_from_env_strict() {
+        std::env::set_var("STOP_STRICT__RETRYING__STOP__ATTEMPTS", "three");
+        let code = StopStrategy {
+            attempts: Some(1),
+            duration: None,
+        };
+        stop_from_env("STOP_STRICT", code, EnvErrors::Panic);
+    }
+
+    #[test]
+    fn test_env_errors() {
+        std::env::set_var("ENV_ERRORS__RETRYING__STOP__ATTEMPTS", "three");
+        let read = || crate::override_by_env(5u32, "ENV_ERRORS", "RETRYING__STOP__ATTEMPTS");
+
+        let result = with_env_errors(EnvErrors::Ignore, || (env_errors(), read()));
+        assert_eq!(result.unwrap(), (EnvErrors::Ignore, 5));
+
+        let result = with_env_errors(EnvErrors::Warn, || (env_errors(), read()));
+        assert_eq!(result.unwrap(), (EnvErrors::Warn, 5));
+
+        let result = with_env_errors(EnvErrors::Panic, read);
+        let message = result.unwrap_err();
+        assert!(message
+            .downcast_ref::<String>()
+            .unwrap()
+            .contains("ENV_ERRORS"));
+
+        std::env::remove_var("ENV_ERRORS__RETRYING__STOP__ATTEMPTS");
+    }
+}
@@ -0,0 +1,209 @@
+//! Iterator adapters that retry failed items.
+
+use crate::{Policy, Retryable, RetryingContext};
+use std::borrow::Borrow;
+
+/// Extension of iterators with retrying adapters.
+pub trait IteratorExt: Iterator + Sized {
+    /// returns iterator that calls `operation` for every item and retries it using `policy`.
+    /// Result of the last attempt for every item is returned by the iterator.
+    fn retry_each<P, F, R>(self, policy: P, operation: F) -> RetryEach<Self, P, F>
+    where
+        P: Borrow<Policy>,
+        F: FnMut(&Self::Item) -> R,
+        R: Retryable,
+    {
+        RetryEach {
+            iter: self,
+            policy,
+            operation,
+        }
+    }
+}
+
+impl<I: Iterator> IteratorExt for I {}
+
+/// Iterator returned by [`IteratorExt::retry_each`].
+pub struct RetryEach<I, P, F> {
+    iter: I,
+    policy: P,
+    operation: F,
+}
+
+impl<I, P, F, R> Iterator for RetryEach<I, P, F>
+where
+    I: Iterator,
+    P: Borrow<Policy>,
+    F: FnMut(&I::Item) -> R,
+    R: Retryable,
+{
+    type Item = R;
+
+    fn next(&mut self) -> Option<R> {
+        let item = self.iter.next()?;
+        let operation = &mut self.operation;
+        Some(self.policy.borrow().retry(|| operation(&item)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// returns iterator over items of iterator created by `factory`. When the iterator returns error, it is re-created by
+/// `factory` using `policy` with position of the last successful item (`None` if there is no such item) that is
+/// returned by `position`, so the new iterator can resume from it.
+///
+/// Stop and wait strategies of `policy` are applied to consecutive errors, so the context is reset after every
+/// successful item. The error is returned and the iterator ends when `policy` stops retrying.
+pub fn retry_iter<P, I, T, E, K, G, F>(
+    policy: P,
+    position: G,
+    factory: F,
+) -> RetryIter<P, I, K, G, F>
+where
+    P: Borrow<Policy>,
+    I: Iterator<Item = Result<T, E>>,
+    G: FnMut(&T) -> K,
+    F: FnMut(Option<&K>) -> I,
+{
+    RetryIter {
+        policy,
+        position,
+        factory,
+        iter: None,
+        last: None,
+        ctx: RetryingContext::new(),
+        done: false,
+    }
+}
+
+/// Iterator returned by [`retry_iter`].
+pub struct RetryIter<P, I, K, G, F> {
+    policy: P,
+    position: G,
+    factory: F,
+    iter: Option<I>,
+    last: Option<K>,
+    ctx: RetryingContext,
+    done: bool,
+}
+
+impl<P, I, T, E, K, G, F> Iterator for RetryIter<P, I, K, G, F>
+where
+    P: Borrow<Policy>,
+    I: Iterator<Item = Result<T, E>>,
+    G: FnMut(&T) -> K,
+    F: FnMut(Option<&K>) -> I,
+{
+    type Item = Result<T, E>;
+
+    fn next(&mut self) -> Option<Result<T, E>> {
+        while !self.done {
+            if self.iter.is_none() {
+                self.iter = Some((self.factory)(self.last.as_ref()));
+            }
+            let result = self.iter.as_mut().and_then(Iterator::next);
+            match result {
+                None => self.done = true,
+                Some(Ok(item)) => {
+                    self.last = Some((self.position)(&item));
+                    self.ctx = RetryingContext::new();
+                    return Some(Ok(item));
+                }
+                Some(Err(err)) => {
+                    let result = Err(err);
+                    let policy = self.policy.borrow();
+                    if !policy.should_retry(&result, &self.ctx) {
+                        self.done = true;
+                        return Some(result);
+                    }
+                    self.iter = None;
+                    self.ctx.add_attempt();
+                    let wait = policy.wait().wait_duration(&self.ctx);
+                    self.ctx.add_wait(wait);
+                    crate::sleep_sync(wait);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stop::StopAttempts;
+    use crate::wait::WaitFixed;
+    use crate::Duration;
+
+    fn policy(attempts: u32) -> Policy {
+        Policy::new(
+            Box::new(StopAttempts::new(attempts)),
+            Box::new(WaitFixed::new(Duration::ZERO)),
+        )
+    }
+
+    #[test]
+    fn test_retry_each() {
+        let mut calls = 0;
+        let results: Vec<Result<u32, u32>> = [1, 2, 3]
+            .into_iter()
+            .retry_each(policy(2), |item| {
+                calls += 1;
+                if *item == 2 {
+                    Err(*item)
+                } else {
+                    Ok(*item * 10)
+                }
+            })
+            .collect();
+
+        assert_eq!(results, vec![Ok(10), Err(2), Ok(30)]);
+        assert_eq!(calls, 4);
+    }
+
+    fn failing_iter(start: u32, fail_at: u32) -> impl Iterator<Item = Result<u32, String>> {
+        (start..=5).map(move |item| {
+            if item == fail_at {
+                Err(format!("failed at {}", item))
+            } else {
+                Ok(item)
+            }
+        })
+    }
+
+    #[test]
+    fn test_retry_iter() {
+        let policy = policy(2);
+
+        let mut fail_at = vec![4, 2];
+        let mut starts = vec![];
+        let items: Vec<_> = retry_iter(
+            &policy,
+            |item: &u32| *item,
+            |last| {
+                let start = last.map_or(1, |last| last + 1);
+                starts.push(start);
+                failing_iter(start, fail_at.pop().unwrap_or(u32::MAX))
+            },
+        )
+        .collect();
+        assert_eq!(items, vec![Ok(1), Ok(2), Ok(3), Ok(4), Ok(5)]);
+        assert_eq!(starts, vec![1, 2, 4]);
+
+        let mut starts = vec![];
+        let items: Vec<_> = retry_iter(
+            &policy,
+            |item: &u32| *item,
+            |last| {
+                let start = last.map_or(1, |last| last + 1);
+                starts.push(start);
+                failing_iter(start, 2)
+            },
+        )
+        .collect();
+        assert_eq!(items, vec![Ok(1), Err(String::from("failed at 2"))]);
+        assert_eq!(starts, vec![1, 2]);
+    }
+}
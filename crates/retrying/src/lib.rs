@@ -1,13 +1,35 @@
 pub use rand;
-pub use retrying_core::retry;
+pub use retrying_core::{policy, retry};
 pub use std::thread::sleep as sleep_sync;
 pub use std::time::Duration;
 use std::time::SystemTime;
 
+pub mod abort;
+pub mod cancel;
 pub mod envs;
+pub mod iter;
+pub mod metrics;
+#[cfg(all(feature = "tower", any(feature = "tokio", feature = "async_std")))]
+pub mod middleware;
+#[cfg(feature = "opentelemetry")]
+mod otel;
+mod policy;
+mod retryable;
+pub mod schedule;
 pub mod stop;
+#[cfg(all(feature = "stream", any(feature = "tokio", feature = "async_std")))]
+pub mod stream;
 pub mod wait;
 
+pub use envs::reload_env;
+pub use iter::{retry_iter, IteratorExt};
+#[cfg(all(feature = "tower", any(feature = "tokio", feature = "async_std")))]
+pub use middleware::RetryingLayer;
+pub use policy::{Policy, PolicyDefinition, PolicyRegistry, PolicySlot};
+pub use retryable::{Outcome, Retryable};
+#[cfg(all(feature = "stream", any(feature = "tokio", feature = "async_std")))]
+pub use stream::{retry_stream, StreamExt};
+
 #[cfg(all(feature = "tokio", feature = "async_std"))]
 compile_error!(
     "feature \"tokio\" and \"async_std\" cannot be enabled at the same time for retrying"
@@ -47,6 +69,7 @@ impl fmt::Display for RetryingError {
 pub struct RetryingContext {
     attempt_num: u32,
     start_time: SystemTime,
+    waited: Duration,
 }
 
 impl RetryingContext {
@@ -54,6 +77,18 @@ impl RetryingContext {
         RetryingContext {
             attempt_num: 1,
             start_time: ::std::time::SystemTime::now(),
+            waited: Duration::ZERO,
+        }
+    }
+
+    /// context of `attempt_num` attempt that started `elapsed` time ago, used for simulation of retry loop.
+    pub(crate) fn simulated(attempt_num: u32, elapsed: Duration) -> Self {
+        RetryingContext {
+            attempt_num,
+            start_time: SystemTime::now()
+                .checked_sub(elapsed)
+                .unwrap_or(SystemTime::UNIX_EPOCH),
+            waited: elapsed,
         }
     }
 
@@ -61,9 +96,23 @@ impl RetryingContext {
         self.start_time
     }
 
+    /// returns number of the current attempt, the first attempt has number 1.
+    pub fn attempt_num(&self) -> u32 {
+        self.attempt_num
+    }
+
     pub fn add_attempt(&mut self) {
         self.attempt_num += 1;
     }
+
+    /// returns total time of waiting between attempts.
+    pub fn waited(&self) -> Duration {
+        self.waited
+    }
+
+    pub fn add_wait(&mut self, duration: Duration) {
+        self.waited += duration;
+    }
 }
 
 impl Default for RetryingContext {
@@ -72,35 +121,25 @@ impl Default for RetryingContext {
     }
 }
 
+/// parses duration from number of seconds (`1.5`) or from human-readable string with units (`500ms`, `2m`, `1h30m`).
+/// Uses the same parser as `retry` macros, so values in code and in OS environment variables have the same format.
+pub fn parse_duration(value: &str) -> Result<Duration, RetryingError> {
+    retrying_config::parse_duration(value).map_err(|e| RetryingError::new(e.message()))
+}
+
 /// read retrying environment using `prefix` and `name` and return the value from environment if it exists and has correct format.
-/// Otherwise method prints error to stderr and returns `original` value.
-/// This method is a part of developer API and should not be used directly (it is public because `retry` macros uses it together with `envs_prefix` configuration option).
+/// Otherwise method handles error according to [`envs::env_errors`] and returns `original` value.
+/// This method is a part of developer API and should not be used directly.
 pub fn override_by_env<T: FromStr>(original: T, prefix: &str, name: &str) -> T {
-    let os_variable = format!("{}__{}", prefix, name);
-
-    match get_env_case_insensitive(&os_variable) {
-        Ok(Some(v)) => match v.parse::<T>() {
-            Ok(parsed) => parsed,
-            Err(_) => {
-                eprint!(
-                    "Failed to parse OS env variable '{}' with value '{}'.",
-                    os_variable, v
-                );
-                original
-            }
-        },
-        Ok(None) => original,
-        Err(RetryingError { msg }) => {
-            eprint!(
-                "Failed to get OS env variable '{}'. Error: {} ",
-                os_variable, msg
-            );
-            original
-        }
-    }
+    let mut reader = envs::EnvReader::new(prefix);
+    let value = reader.value(name);
+    reader.report(envs::env_errors());
+    value.unwrap_or(original)
 }
 
-fn get_env_case_insensitive(environment: &String) -> Result<Option<String>, RetryingError> {
+pub(crate) fn get_env_case_insensitive(
+    environment: &String,
+) -> Result<Option<String>, RetryingError> {
     if environment.is_empty() {
         Ok(None)
     } else {
@@ -145,6 +184,15 @@ mod tests {
         std::env::remove_var("MY_METHOD__TEST")
     }
 
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("1h30m"), Ok(Duration::from_secs(5400)));
+        assert_eq!(
+            parse_duration("10 parsecs"),
+            Err(RetryingError::new("Failed to parse duration '10 parsecs'. Unknown unit 'parsecs'. Expected number of seconds or value with units like `500ms`, `2m`, `1h30m`"))
+        );
+    }
+
     #[test]
     fn test_get_env_case_insensitive() {
         let testing_env = String::from("MY_METHOD__RETRYING__STOP__ATTEMPTS");
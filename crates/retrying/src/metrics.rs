@@ -0,0 +1,329 @@
+//! Metrics of functions with `retry` macros.
+//!
+//! Metrics are collected only with `metrics` feature. Each function has counters of calls, attempts and results and
+//! histograms of total latency and sleep time between attempts. Metrics are labelled by function name and optional
+//! label from `metrics_label` configuration option and can be read by [`snapshot`] (for example, by exporter of
+//! application metrics).
+
+use crate::{Duration, Outcome, RetryingContext};
+
+#[cfg(feature = "metrics")]
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+#[cfg(feature = "metrics")]
+use std::sync::Mutex;
+
+/// Upper bounds of histogram buckets. Values greater than the last bound are counted in the overflow bucket with
+/// `Duration::MAX` bound.
+#[cfg(feature = "metrics")]
+pub const BUCKETS: [Duration; 12] = [
+    Duration::from_millis(1),
+    Duration::from_millis(5),
+    Duration::from_millis(10),
+    Duration::from_millis(50),
+    Duration::from_millis(100),
+    Duration::from_millis(500),
+    Duration::from_secs(1),
+    Duration::from_secs(5),
+    Duration::from_secs(10),
+    Duration::from_secs(30),
+    Duration::from_secs(60),
+    Duration::from_secs(300),
+];
+
+#[cfg(feature = "metrics")]
+static REGISTRY: Mutex<Vec<&'static FunctionMetrics>> = Mutex::new(Vec::new());
+
+/// returns metrics of all functions with `retry` macros that were called at least once.
+#[cfg(feature = "metrics")]
+pub fn snapshot() -> Vec<FunctionMetricsSnapshot> {
+    let registry = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+    registry.iter().map(|metrics| metrics.snapshot()).collect()
+}
+
+/// Metrics of function with `retry` macros. `retry` macros creates one static instance per function.
+/// Metrics are collected with `metrics` feature and retries are recorded as events of the current OpenTelemetry span
+/// with `opentelemetry` feature, otherwise all methods do nothing.
+/// This struct is a part of developer API and should not be used directly.
+pub struct FunctionMetrics {
+    function: &'static str,
+    label: Option<&'static str>,
+    #[cfg(feature = "metrics")]
+    counters: Counters,
+}
+
+impl FunctionMetrics {
+    pub const fn new(function: &'static str, label: Option<&'static str>) -> FunctionMetrics {
+        FunctionMetrics {
+            function,
+            label,
+            #[cfg(feature = "metrics")]
+            counters: Counters::new(),
+        }
+    }
+
+    pub fn function(&self) -> &'static str {
+        self.function
+    }
+
+    pub fn label(&self) -> Option<&'static str> {
+        self.label
+    }
+
+    /// records failed attempt that is retried after `wait`.
+    #[inline]
+    #[cfg_attr(not(feature = "opentelemetry"), allow(unused_variables))]
+    pub fn record_retry(&'static self, ctx: &RetryingContext, wait: Duration) {
+        #[cfg(feature = "opentelemetry")]
+        crate::otel::record_retry(self, ctx, wait);
+    }
+
+    /// records finished call of function. `Outcome::Retry` means that retrying was stopped by stop strategy and
+    /// `Outcome::Abort` means that error is not retryable.
+    #[inline]
+    #[cfg_attr(
+        not(any(feature = "metrics", feature = "opentelemetry")),
+        allow(unused_variables)
+    )]
+    pub fn record<E>(&'static self, ctx: &RetryingContext, outcome: &Outcome<'_, E>) {
+        #[cfg(feature = "metrics")]
+        self.record_counters(ctx, outcome);
+        #[cfg(feature = "opentelemetry")]
+        crate::otel::record_outcome(self, ctx, outcome);
+    }
+
+    /// records call of async function that was stopped by `cancel` future.
+    #[inline]
+    #[cfg_attr(
+        not(any(feature = "metrics", feature = "opentelemetry")),
+        allow(unused_variables)
+    )]
+    pub fn record_cancelled(&'static self, ctx: &RetryingContext) {
+        #[cfg(feature = "metrics")]
+        {
+            self.record_call(ctx);
+            self.counters.cancelled.fetch_add(1, Ordering::Relaxed);
+        }
+        #[cfg(feature = "opentelemetry")]
+        crate::otel::record_cancelled(self, ctx);
+    }
+
+    #[cfg(feature = "metrics")]
+    fn record_counters<E>(&'static self, ctx: &RetryingContext, outcome: &Outcome<'_, E>) {
+        let counters = &self.counters;
+        self.record_call(ctx);
+        match outcome {
+            Outcome::Success => {
+                counters.successes.fetch_add(1, Ordering::Relaxed);
+                if ctx.attempt_num > 1 {
+                    counters
+                        .successes_after_retry
+                        .fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            Outcome::Retry(_) => {
+                counters.exhausted.fetch_add(1, Ordering::Relaxed);
+            }
+            Outcome::Abort(_) => {
+                counters.non_retryable.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// records counters and histograms common for all outcomes.
+    #[cfg(feature = "metrics")]
+    fn record_call(&'static self, ctx: &RetryingContext) {
+        let counters = &self.counters;
+        if !counters.registered.swap(true, Ordering::Relaxed) {
+            REGISTRY
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(self);
+        }
+
+        counters.calls.fetch_add(1, Ordering::Relaxed);
+        counters
+            .attempts
+            .fetch_add(u64::from(ctx.attempt_num), Ordering::Relaxed);
+        counters
+            .latency
+            .observe(ctx.started_at().elapsed().unwrap_or_default());
+        counters.sleep.observe(ctx.waited());
+    }
+
+    #[cfg(feature = "metrics")]
+    pub fn snapshot(&self) -> FunctionMetricsSnapshot {
+        let counters = &self.counters;
+        FunctionMetricsSnapshot {
+            function: self.function,
+            label: self.label,
+            calls: counters.calls.load(Ordering::Relaxed),
+            attempts: counters.attempts.load(Ordering::Relaxed),
+            successes: counters.successes.load(Ordering::Relaxed),
+            successes_after_retry: counters.successes_after_retry.load(Ordering::Relaxed),
+            exhausted: counters.exhausted.load(Ordering::Relaxed),
+            non_retryable: counters.non_retryable.load(Ordering::Relaxed),
+            cancelled: counters.cancelled.load(Ordering::Relaxed),
+            latency: counters.latency.snapshot(),
+            sleep: counters.sleep.snapshot(),
+        }
+    }
+}
+
+#[cfg(feature = "metrics")]
+struct Counters {
+    registered: AtomicBool,
+    calls: AtomicU64,
+    attempts: AtomicU64,
+    successes: AtomicU64,
+    successes_after_retry: AtomicU64,
+    exhausted: AtomicU64,
+    non_retryable: AtomicU64,
+    cancelled: AtomicU64,
+    latency: Histogram,
+    sleep: Histogram,
+}
+
+#[cfg(feature = "metrics")]
+impl Counters {
+    const fn new() -> Counters {
+        Counters {
+            registered: AtomicBool::new(false),
+            calls: AtomicU64::new(0),
+            attempts: AtomicU64::new(0),
+            successes: AtomicU64::new(0),
+            successes_after_retry: AtomicU64::new(0),
+            exhausted: AtomicU64::new(0),
+            non_retryable: AtomicU64::new(0),
+            cancelled: AtomicU64::new(0),
+            latency: Histogram::new(),
+            sleep: Histogram::new(),
+        }
+    }
+}
+
+/// Metrics of function at the moment of [`snapshot`] call.
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionMetricsSnapshot {
+    pub function: &'static str,
+    pub label: Option<&'static str>,
+    /// number of finished calls.
+    pub calls: u64,
+    /// number of attempts in all finished calls.
+    pub attempts: u64,
+    pub successes: u64,
+    /// number of successful calls that required more than one attempt.
+    pub successes_after_retry: u64,
+    /// number of failed calls stopped by stop strategy.
+    pub exhausted: u64,
+    /// number of calls failed with error that is not retryable.
+    pub non_retryable: u64,
+    /// number of calls stopped by `cancel` future.
+    pub cancelled: u64,
+    /// total duration of calls (including sleep time).
+    pub latency: HistogramSnapshot,
+    /// total sleep time between attempts per call.
+    pub sleep: HistogramSnapshot,
+}
+
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistogramSnapshot {
+    /// pairs of bucket upper bound and number of values in the bucket (not cumulative).
+    pub buckets: Vec<(Duration, u64)>,
+    pub count: u64,
+    pub sum: Duration,
+}
+
+#[cfg(feature = "metrics")]
+struct Histogram {
+    buckets: [AtomicU64; BUCKETS.len() + 1],
+    count: AtomicU64,
+    sum_nanos: AtomicU64,
+}
+
+#[cfg(feature = "metrics")]
+impl Histogram {
+    const fn new() -> Histogram {
+        #[allow(clippy::declare_interior_mutable_const)]
+        const ZERO: AtomicU64 = AtomicU64::new(0);
+        Histogram {
+            buckets: [ZERO; BUCKETS.len() + 1],
+            count: ZERO,
+            sum_nanos: ZERO,
+        }
+    }
+
+    fn observe(&self, value: Duration) {
+        let bucket = BUCKETS
+            .iter()
+            .position(|bound| value <= *bound)
+            .unwrap_or(BUCKETS.len());
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        let nanos = u64::try_from(value.as_nanos()).unwrap_or(u64::MAX);
+        self.sum_nanos.fetch_add(nanos, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> HistogramSnapshot {
+        let bounds = BUCKETS.iter().copied().chain(Some(Duration::MAX));
+        HistogramSnapshot {
+            buckets: bounds
+                .zip(&self.buckets)
+                .map(|(bound, count)| (bound, count.load(Ordering::Relaxed)))
+                .collect(),
+            count: self.count.load(Ordering::Relaxed),
+            sum: Duration::from_nanos(self.sum_nanos.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram() {
+        let histogram = Histogram::new();
+        histogram.observe(Duration::ZERO);
+        histogram.observe(Duration::from_millis(7));
+        histogram.observe(Duration::from_secs(3600));
+
+        let snapshot = histogram.snapshot();
+        assert_eq!(snapshot.count, 3);
+        assert_eq!(snapshot.sum, Duration::from_millis(3600007));
+        assert_eq!(snapshot.buckets.len(), BUCKETS.len() + 1);
+        assert_eq!(snapshot.buckets[0], (Duration::from_millis(1), 1));
+        assert_eq!(snapshot.buckets[2], (Duration::from_millis(10), 1));
+        assert_eq!(snapshot.buckets[BUCKETS.len()], (Duration::MAX, 1));
+    }
+
+    #[test]
+    fn test_function_metrics_record() {
+        static METRICS: FunctionMetrics = FunctionMetrics::new("test_record", Some("label"));
+
+        let mut ctx = RetryingContext::new();
+        METRICS.record(&ctx, &Outcome::<()>::Success);
+        ctx.add_attempt();
+        ctx.add_wait(Duration::from_millis(20));
+        METRICS.record(&ctx, &Outcome::<()>::Success);
+        METRICS.record(&ctx, &Outcome::Retry(&()));
+        METRICS.record(&ctx, &Outcome::Abort(&()));
+
+        let snapshot = METRICS.snapshot();
+        assert_eq!(snapshot.function, "test_record");
+        assert_eq!(snapshot.label, Some("label"));
+        assert_eq!(snapshot.calls, 4);
+        assert_eq!(snapshot.attempts, 7);
+        assert_eq!(snapshot.successes, 2);
+        assert_eq!(snapshot.successes_after_retry, 1);
+        assert_eq!(snapshot.exhausted, 1);
+        assert_eq!(snapshot.non_retryable, 1);
+        assert_eq!(snapshot.sleep.sum, Duration::from_millis(60));
+        assert_eq!(snapshot.latency.count, 4);
+
+        assert!(super::snapshot()
+            .iter()
+            .any(|metrics| metrics.function == "test_record"));
+    }
+}
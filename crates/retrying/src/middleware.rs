@@ -0,0 +1,204 @@
+//! `tower` middleware that retries requests (available with `tower` feature and `tokio` or `async_std` feature).
+//!
+//! [`RetryingLayer`] wraps service into [`Retrying`] service that clones the request for every attempt and retries
+//! results classified as retryable by [`Classify`] using stop and wait strategies of [`Policy`].
+
+use crate::stop::Stop;
+use crate::wait::Wait;
+use crate::{Policy, RetryingContext};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Classifier of responses and errors of service.
+///
+/// Implemented for functions `Fn(&Result<Response, Error>) -> bool`, so response with retryable status can be
+/// retried as well as error.
+pub trait Classify<Res, E> {
+    /// returns `true` if request with this result should be retried.
+    fn is_retryable(&self, result: &Result<Res, E>) -> bool;
+}
+
+impl<Res, E, F> Classify<Res, E> for F
+where
+    F: Fn(&Result<Res, E>) -> bool,
+{
+    fn is_retryable(&self, result: &Result<Res, E>) -> bool {
+        self(result)
+    }
+}
+
+/// Default classifier that retries all errors and no responses.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RetryErrors;
+
+impl<Res, E> Classify<Res, E> for RetryErrors {
+    fn is_retryable(&self, result: &Result<Res, E>) -> bool {
+        result.is_err()
+    }
+}
+
+/// `tower` layer that applies retrying to services.
+#[derive(Clone)]
+pub struct RetryingLayer<C = RetryErrors> {
+    policy: Arc<Policy>,
+    classifier: C,
+}
+
+impl RetryingLayer {
+    pub fn new<S, W>(stop: S, wait: W) -> RetryingLayer
+    where
+        S: Stop + Send + Sync + 'static,
+        W: Wait + Send + Sync + 'static,
+    {
+        RetryingLayer::from_policy(Arc::new(Policy::new(Box::new(stop), Box::new(wait))))
+    }
+
+    /// creates layer from existing policy (for example, from [`PolicyRegistry`](crate::PolicyRegistry)).
+    pub fn from_policy(policy: Arc<Policy>) -> RetryingLayer {
+        RetryingLayer {
+            policy,
+            classifier: RetryErrors,
+        }
+    }
+}
+
+impl<C> RetryingLayer<C> {
+    /// replaces classifier of responses and errors.
+    pub fn with_classifier<T>(self, classifier: T) -> RetryingLayer<T> {
+        RetryingLayer {
+            policy: self.policy,
+            classifier,
+        }
+    }
+}
+
+impl<S, C: Clone> Layer<S> for RetryingLayer<C> {
+    type Service = Retrying<S, C>;
+
+    fn layer(&self, inner: S) -> Retrying<S, C> {
+        Retrying {
+            inner,
+            policy: self.policy.clone(),
+            classifier: self.classifier.clone(),
+        }
+    }
+}
+
+/// `tower` service that retries requests of inner service. Created by [`RetryingLayer`].
+#[derive(Clone)]
+pub struct Retrying<S, C = RetryErrors> {
+    inner: S,
+    policy: Arc<Policy>,
+    classifier: C,
+}
+
+impl<S, C, Req> Service<Req> for Retrying<S, C>
+where
+    S: Service<Req> + Clone + Send + 'static,
+    S::Future: Send,
+    S::Response: Send,
+    S::Error: Send,
+    C: Classify<S::Response, S::Error> + Clone + Send + Sync + 'static,
+    Req: Clone + Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Req) -> Self::Future {
+        // the service that was polled ready is used for the first attempt, its clone stays for next calls
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let policy = self.policy.clone();
+        let classifier = self.classifier.clone();
+
+        Box::pin(async move {
+            let mut ctx = RetryingContext::new();
+            let mut result = inner.call(request.clone()).await;
+            while classifier.is_retryable(&result) && !policy.stop().stop_execution(&ctx) {
+                ctx.add_attempt();
+                let wait = policy.wait().wait_duration(&ctx);
+                ctx.add_wait(wait);
+                crate::sleep_async(wait).await;
+
+                result = match std::future::poll_fn(|cx| inner.poll_ready(cx)).await {
+                    Ok(()) => inner.call(request.clone()).await,
+                    Err(err) => Err(err),
+                };
+            }
+            result
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stop::StopAttempts;
+    use crate::wait::WaitFixed;
+    use crate::Duration;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use tower::{service_fn, ServiceBuilder, ServiceExt};
+
+    fn layer() -> RetryingLayer {
+        RetryingLayer::new(
+            StopAttempts::new(3),
+            WaitFixed::new(Duration::from_millis(1)),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_retrying_errors() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let service = {
+            let calls = calls.clone();
+            ServiceBuilder::new()
+                .layer(layer())
+                .service(service_fn(move |request: u32| {
+                    let calls = calls.fetch_add(1, Ordering::SeqCst) + 1;
+                    async move {
+                        if calls < 2 {
+                            Err(format!("failed {}", calls))
+                        } else {
+                            Ok(request * 10)
+                        }
+                    }
+                }))
+        };
+
+        assert_eq!(service.clone().oneshot(1).await, Ok(10));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        let service = ServiceBuilder::new()
+            .layer(layer())
+            .service(service_fn(|_: u32| async { Err::<u32, _>("failed") }));
+        assert_eq!(service.oneshot(1).await, Err("failed"));
+    }
+
+    #[tokio::test]
+    async fn test_retrying_classifier() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let service = {
+            let calls = calls.clone();
+            ServiceBuilder::new()
+                .layer(layer().with_classifier(|result: &Result<u16, String>| {
+                    matches!(result, Ok(503) | Err(_))
+                }))
+                .service(service_fn(move |_: &'static str| {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    async { Ok::<u16, String>(503) }
+                }))
+        };
+
+        assert_eq!(service.oneshot("/health").await, Ok(503));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+}
@@ -0,0 +1,66 @@
+//! OpenTelemetry annotations of the current span (available with `opentelemetry` feature).
+//!
+//! Every retry adds `retry` event with `retry.attempt`, `retry.wait_ms` and `retry.outcome` attributes to the current span.
+//! When function with `retry` macros finishes, the same attributes are set on the span itself, where `retry.attempt`
+//! is the total number of attempts, `retry.wait_ms` is the total time spent in waits and `retry.outcome` is one of
+//! `success`, `exhausted`, `non_retryable` or `cancelled`.
+
+use crate::metrics::FunctionMetrics;
+use crate::{Duration, Outcome, RetryingContext};
+use opentelemetry::trace::get_active_span;
+use opentelemetry::KeyValue;
+
+const RETRY_EVENT: &str = "retry";
+const FUNCTION: &str = "retry.function";
+const LABEL: &str = "retry.label";
+const ATTEMPT: &str = "retry.attempt";
+const WAIT_MS: &str = "retry.wait_ms";
+const OUTCOME: &str = "retry.outcome";
+
+pub(crate) fn record_retry(metrics: &FunctionMetrics, ctx: &RetryingContext, wait: Duration) {
+    get_active_span(|span| {
+        let mut attributes = vec![
+            KeyValue::new(FUNCTION, metrics.function()),
+            KeyValue::new(ATTEMPT, i64::from(ctx.attempt_num)),
+            KeyValue::new(WAIT_MS, millis(wait)),
+            KeyValue::new(OUTCOME, "retry"),
+        ];
+        if let Some(label) = metrics.label() {
+            attributes.push(KeyValue::new(LABEL, label));
+        }
+        span.add_event(RETRY_EVENT, attributes);
+    });
+}
+
+pub(crate) fn record_outcome<E>(
+    metrics: &FunctionMetrics,
+    ctx: &RetryingContext,
+    outcome: &Outcome<'_, E>,
+) {
+    let outcome = match outcome {
+        Outcome::Success => "success",
+        Outcome::Retry(_) => "exhausted",
+        Outcome::Abort(_) => "non_retryable",
+    };
+    set_outcome(metrics, ctx, outcome);
+}
+
+pub(crate) fn record_cancelled(metrics: &FunctionMetrics, ctx: &RetryingContext) {
+    set_outcome(metrics, ctx, "cancelled");
+}
+
+fn set_outcome(metrics: &FunctionMetrics, ctx: &RetryingContext, outcome: &'static str) {
+    get_active_span(|span| {
+        span.set_attribute(KeyValue::new(FUNCTION, metrics.function()));
+        if let Some(label) = metrics.label() {
+            span.set_attribute(KeyValue::new(LABEL, label));
+        }
+        span.set_attribute(KeyValue::new(ATTEMPT, i64::from(ctx.attempt_num)));
+        span.set_attribute(KeyValue::new(WAIT_MS, millis(ctx.waited())));
+        span.set_attribute(KeyValue::new(OUTCOME, outcome));
+    });
+}
+
+fn millis(duration: Duration) -> i64 {
+    i64::try_from(duration.as_millis()).unwrap_or(i64::MAX)
+}
@@ -0,0 +1,642 @@
+use crate::abort::RetryAbort;
+#[cfg(any(feature = "tokio", feature = "async_std"))]
+use crate::cancel::{Cancellation, Cancelled};
+use crate::schedule::Schedule;
+use crate::stop::{Stop, StopNever, StopStrategy};
+use crate::wait::{Wait, WaitFixed, WaitStrategy};
+use crate::{Duration, Outcome, Retryable, RetryingContext};
+use arc_swap::ArcSwapOption;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// Pair of stop and wait strategies that can be shared between functions (see [`PolicyRegistry`]).
+pub struct Policy {
+    stop: Box<dyn Stop + Send + Sync>,
+    wait: Box<dyn Wait + Send + Sync>,
+}
+
+impl Policy {
+    pub fn new(stop: Box<dyn Stop + Send + Sync>, wait: Box<dyn Wait + Send + Sync>) -> Policy {
+        Policy { stop, wait }
+    }
+
+    /// builds policy from runtime strategies. Policy without wait strategy doesn't wait between attempts.
+    pub fn from_strategies(stop: StopStrategy, wait: Option<WaitStrategy>) -> Policy {
+        Policy {
+            stop: stop.build(),
+            wait: wait.map_or_else(
+                || Box::new(WaitFixed::new(Duration::ZERO)) as _,
+                WaitStrategy::build,
+            ),
+        }
+    }
+
+    pub fn stop(&self) -> &(dyn Stop + Send + Sync) {
+        self.stop.as_ref()
+    }
+
+    pub fn wait(&self) -> &(dyn Wait + Send + Sync) {
+        self.wait.as_ref()
+    }
+
+    /// calls `operation` until its result is not [`Outcome::Retry`] or the stop strategy stops retrying and returns the last result.
+    pub fn retry<R: Retryable, F: FnMut() -> R>(&self, mut operation: F) -> R {
+        let mut ctx = RetryingContext::new();
+        loop {
+            let result = operation();
+            if !self.should_retry(&result, &ctx) {
+                return result;
+            }
+            ctx.add_attempt();
+            let wait = self.wait.wait_duration(&ctx);
+            ctx.add_wait(wait);
+            crate::sleep_sync(wait);
+        }
+    }
+
+    /// version of [`Policy::retry`] that waits between attempts using `abort` handle and returns the last result
+    /// as soon as retries are aborted.
+    pub fn retry_with_abort<R: Retryable, F: FnMut() -> R>(
+        &self,
+        abort: &RetryAbort,
+        mut operation: F,
+    ) -> R {
+        let mut ctx = RetryingContext::new();
+        loop {
+            let result = operation();
+            if !self.should_retry(&result, &ctx) || abort.is_aborted() {
+                return result;
+            }
+            ctx.add_attempt();
+            let wait = self.wait.wait_duration(&ctx);
+            ctx.add_wait(wait);
+            if !abort.sleep(wait) {
+                return result;
+            }
+        }
+    }
+
+    /// asynchronous version of [`Policy::retry`].
+    #[cfg(any(feature = "tokio", feature = "async_std"))]
+    pub async fn retry_async<R, Fut, F>(&self, mut operation: F) -> R
+    where
+        R: Retryable,
+        Fut: std::future::Future<Output = R>,
+        F: FnMut() -> Fut,
+    {
+        let mut ctx = RetryingContext::new();
+        loop {
+            {
+                let result = operation().await;
+                if !self.should_retry(&result, &ctx) {
+                    return result;
+                }
+            }
+            ctx.add_attempt();
+            let wait = self.wait.wait_duration(&ctx);
+            ctx.add_wait(wait);
+            crate::sleep_async(wait).await;
+        }
+    }
+
+    /// asynchronous version of [`Policy::retry`] that races every attempt and wait against `cancel` future and
+    /// returns `Err(Cancelled)` as soon as the future completes.
+    #[cfg(any(feature = "tokio", feature = "async_std"))]
+    pub async fn retry_async_with_cancel<R, Fut, F, C>(
+        &self,
+        cancel: C,
+        mut operation: F,
+    ) -> Result<R, Cancelled>
+    where
+        R: Retryable,
+        Fut: std::future::Future<Output = R>,
+        F: FnMut() -> Fut,
+        C: std::future::IntoFuture,
+    {
+        let mut cancel = Cancellation::new(cancel);
+        let mut ctx = RetryingContext::new();
+        loop {
+            {
+                let result = cancel.run(operation()).await.ok_or(Cancelled)?;
+                if !self.should_retry(&result, &ctx) {
+                    return Ok(result);
+                }
+            }
+            ctx.add_attempt();
+            let wait = self.wait.wait_duration(&ctx);
+            ctx.add_wait(wait);
+            cancel
+                .run(crate::sleep_async(wait))
+                .await
+                .ok_or(Cancelled)?;
+        }
+    }
+
+    /// returns at most `attempts` planned attempts of the policy (see [`schedule`](crate::schedule)).
+    pub fn simulate(&self, attempts: u32) -> Schedule {
+        crate::schedule::simulate(self.stop(), self.wait(), attempts)
+    }
+
+    /// returns planned attempts of the policy until it stops retrying (at most [`Schedule::MAX_ATTEMPTS`]).
+    pub fn schedule(&self) -> Schedule {
+        self.simulate(Schedule::MAX_ATTEMPTS)
+    }
+
+    pub(crate) fn should_retry<R: Retryable>(&self, result: &R, ctx: &RetryingContext) -> bool {
+        matches!(result.outcome(), Outcome::Retry(_)) && !self.stop.stop_execution(ctx)
+    }
+}
+
+impl fmt::Display for Policy {
+    /// describes policy in plain words, for example `stops after 3 attempts, waits 1.5s between attempts`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.stop.describe(f)?;
+        write!(f, ", ")?;
+        self.wait.describe(f)?;
+        write!(f, " between attempts")
+    }
+}
+
+impl fmt::Debug for Policy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        struct Describe<'a>(&'a dyn Fn(&mut fmt::Formatter) -> fmt::Result);
+
+        impl fmt::Debug for Describe<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                (self.0)(f)
+            }
+        }
+
+        f.debug_struct("Policy")
+            .field("stop", &Describe(&|f| self.stop.describe(f)))
+            .field("wait", &Describe(&|f| self.wait.describe(f)))
+            .finish()
+    }
+}
+
+impl Default for Policy {
+    /// policy that never stops and doesn't wait between attempts.
+    fn default() -> Self {
+        Policy::new(
+            Box::new(StopNever {}),
+            Box::new(WaitFixed::new(Duration::ZERO)),
+        )
+    }
+}
+
+/// Stop and wait strategies of policy defined by `retrying::policy!` macros.
+///
+/// The definition contains only configuration from code, OS environment variables are applied by functions with
+/// `retry` macros that use the policy and `envs_prefix` option.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PolicyDefinition {
+    pub stop: StopStrategy,
+    pub wait: Option<WaitStrategy>,
+}
+
+impl PolicyDefinition {
+    pub const fn new(stop: StopStrategy, wait: Option<WaitStrategy>) -> PolicyDefinition {
+        PolicyDefinition { stop, wait }
+    }
+
+    /// builds policy that can be used with [`Policy::retry`].
+    pub fn build(&self) -> Policy {
+        Policy::from_strategies(self.stop, self.wait)
+    }
+}
+
+/// Registry of named policies.
+///
+/// Policies are loaded once (on first use by name) and cached, so functions with `retry` macros and `policy="name"`
+/// configuration option don't rebuild strategies and don't read OS environment variables on every call.
+/// Application can atomically replace a policy at any time (for example, from file watcher or admin endpoint) using
+/// [`PolicyRegistry::insert`]; running retry loops finish with the policy they started with and next calls use the new one.
+///
+/// When policy with `name` is not in the registry, the first called function builds it from its macros configuration
+/// and other functions with the same `name` use it as well. Functions with the same `name` and different configuration
+/// are reported as a warning (using `log` crate if `log` feature is enabled or stderr otherwise).
+pub struct PolicyRegistry {
+    policies: Mutex<Option<HashMap<String, PolicyEntry>>>,
+}
+
+#[derive(Default)]
+struct PolicyEntry {
+    policy: Option<Arc<Policy>>,
+    /// configuration of function that built the default policy, `None` for policies inserted by application.
+    config: Option<&'static str>,
+    slots: Vec<&'static PolicySlot>,
+}
+
+impl PolicyEntry {
+    fn set(
+        &mut self,
+        policy: Option<Arc<Policy>>,
+        config: Option<&'static str>,
+    ) -> Option<Arc<Policy>> {
+        for slot in &self.slots {
+            slot.policy.store(policy.clone());
+        }
+        self.config = config;
+        std::mem::replace(&mut self.policy, policy)
+    }
+}
+
+static GLOBAL_REGISTRY: PolicyRegistry = PolicyRegistry::new();
+
+impl PolicyRegistry {
+    pub const fn new() -> PolicyRegistry {
+        PolicyRegistry {
+            policies: Mutex::new(None),
+        }
+    }
+
+    /// registry used by `retry` macros.
+    pub fn global() -> &'static PolicyRegistry {
+        &GLOBAL_REGISTRY
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<Policy>> {
+        let policies = self.policies.lock().unwrap_or_else(|e| e.into_inner());
+        policies
+            .as_ref()
+            .and_then(|p| p.get(name))
+            .and_then(|entry| entry.policy.clone())
+    }
+
+    /// returns policy by `name` or builds it with `default`, caches and returns it if registry doesn't have such policy.
+    pub fn get_or_insert_with<F: FnOnce() -> Policy>(&self, name: &str, default: F) -> Arc<Policy> {
+        self.with_entry(name, |entry| match &entry.policy {
+            Some(policy) => policy.clone(),
+            None => {
+                let policy = Arc::new(default());
+                entry.set(Some(policy.clone()), None);
+                policy
+            }
+        })
+    }
+
+    /// inserts or atomically replaces policy with `name`. Returns previous policy if it exists.
+    pub fn insert(&self, name: &str, policy: Policy) -> Option<Arc<Policy>> {
+        self.with_entry(name, |entry| entry.set(Some(Arc::new(policy)), None))
+    }
+
+    /// removes policy with `name`, so next call of function with such policy loads its default policy again.
+    pub fn remove(&self, name: &str) -> Option<Arc<Policy>> {
+        self.with_entry(name, |entry| entry.set(None, None))
+    }
+
+    fn with_entry<T, F: FnOnce(&mut PolicyEntry) -> T>(&self, name: &str, f: F) -> T {
+        let mut policies = self.policies.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = policies
+            .get_or_insert_with(HashMap::new)
+            .entry(name.to_string())
+            .or_default();
+        f(entry)
+    }
+}
+
+/// Current policy with some name in [`PolicyRegistry`] cached by one function. `retry` macros creates one static slot
+/// per function with `policy="name"` configuration option and the registry updates the slot when the policy is
+/// inserted or removed, so calls read the current policy with a single atomic load without locks and lookups by name.
+/// This struct is a part of developer API and should not be used directly.
+pub struct PolicySlot {
+    policy: ArcSwapOption<Policy>,
+}
+
+impl PolicySlot {
+    pub const fn new() -> PolicySlot {
+        PolicySlot {
+            policy: ArcSwapOption::const_empty(),
+        }
+    }
+
+    /// returns current policy with `name` from `registry`. If the registry doesn't have such policy, it's built by
+    /// `default` and inserted into the registry. `config` describes configuration of `default` policy, so functions
+    /// with the same `name` and different configuration are reported.
+    pub fn get_or_insert_with<F: FnOnce() -> Policy>(
+        &'static self,
+        registry: &PolicyRegistry,
+        name: &str,
+        config: &'static str,
+        default: F,
+    ) -> Arc<Policy> {
+        if let Some(policy) = self.policy.load_full() {
+            return policy;
+        }
+
+        registry.with_entry(name, |entry| {
+            if !entry.slots.iter().any(|slot| std::ptr::eq(*slot, self)) {
+                entry.slots.push(self);
+            }
+            match &entry.policy {
+                Some(policy) => {
+                    if entry.config.map_or(false, |loaded| loaded != config) {
+                        crate::envs::warn(&format!(
+                            "Functions with retrying policy '{}' have different configuration, policy of the first called function is used",
+                            name
+                        ));
+                    }
+                    self.policy.store(Some(policy.clone()));
+                    policy.clone()
+                }
+                None => {
+                    let policy = Arc::new(default());
+                    entry.set(Some(policy.clone()), Some(config));
+                    policy
+                }
+            }
+        })
+    }
+}
+
+impl Default for PolicySlot {
+    fn default() -> Self {
+        PolicySlot::new()
+    }
+}
+
+impl Default for PolicyRegistry {
+    fn default() -> Self {
+        PolicyRegistry::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stop::StopAttempts;
+
+    #[test]
+    fn test_policy_from_strategies() {
+        let mut ctx = RetryingContext::new();
+        let policy = Policy::from_strategies(
+            StopStrategy {
+                attempts: Some(2),
+                duration: None,
+            },
+            None,
+        );
+
+        assert!(!policy.stop().stop_execution(&ctx));
+        assert_eq!(policy.wait().wait_duration(&ctx), Duration::ZERO);
+
+        ctx.add_attempt();
+        assert!(policy.stop().stop_execution(&ctx));
+    }
+
+    #[test]
+    fn test_policy_definition_build() {
+        const DEFINITION: PolicyDefinition = PolicyDefinition::new(
+            StopStrategy {
+                attempts: Some(3),
+                duration: None,
+            },
+            Some(WaitStrategy::Fixed {
+                duration: Duration::from_millis(1),
+            }),
+        );
+
+        let policy = DEFINITION.build();
+        let mut calls = 0;
+        let result = policy.retry(|| {
+            calls += 1;
+            Err::<(), _>(calls)
+        });
+        assert_eq!(result, Err(3));
+        assert_eq!(
+            policy.wait().wait_duration(&RetryingContext::new()),
+            Duration::from_millis(1)
+        );
+    }
+
+    #[test]
+    fn test_registry_get_or_insert_with() {
+        let registry = PolicyRegistry::new();
+        assert!(registry.get("test").is_none());
+
+        let first = registry.get_or_insert_with("test", Policy::default);
+        let second = registry.get_or_insert_with("test", || unreachable!());
+
+        assert!(Arc::ptr_eq(&first, &second));
+    }
+
+    #[test]
+    fn test_registry_insert() {
+        let registry = PolicyRegistry::new();
+        let ctx = RetryingContext::new();
+
+        let old = registry.get_or_insert_with("test", Policy::default);
+        assert!(!old.stop().stop_execution(&ctx));
+
+        let replaced = registry.insert(
+            "test",
+            Policy::new(
+                Box::new(StopAttempts::new(1)),
+                Box::new(WaitFixed::new(Duration::from_secs(1))),
+            ),
+        );
+        assert!(Arc::ptr_eq(&old, &replaced.unwrap()));
+
+        let new = registry.get_or_insert_with("test", || unreachable!());
+        assert!(new.stop().stop_execution(&ctx));
+        assert_eq!(new.wait().wait_duration(&ctx), Duration::from_secs(1));
+
+        assert!(registry.remove("test").is_some());
+        assert!(registry.get("test").is_none());
+    }
+
+    #[test]
+    fn test_policy_slot() {
+        static FIRST: PolicySlot = PolicySlot::new();
+        static SECOND: PolicySlot = PolicySlot::new();
+        let registry = PolicyRegistry::new();
+        let ctx = RetryingContext::new();
+
+        let first = FIRST.get_or_insert_with(&registry, "test", "first", Policy::default);
+        let cached = FIRST.get_or_insert_with(&registry, "test", "first", || unreachable!());
+        let second = SECOND.get_or_insert_with(&registry, "test", "second", || unreachable!());
+        assert!(Arc::ptr_eq(&first, &cached));
+        assert!(Arc::ptr_eq(&first, &second));
+
+        registry.insert(
+            "test",
+            Policy::new(
+                Box::new(StopAttempts::new(1)),
+                Box::new(WaitFixed::new(Duration::ZERO)),
+            ),
+        );
+        for slot in [&FIRST, &SECOND] {
+            let policy = slot.get_or_insert_with(&registry, "test", "first", || unreachable!());
+            assert!(policy.stop().stop_execution(&ctx));
+        }
+
+        registry.remove("test");
+        let policy = SECOND.get_or_insert_with(&registry, "test", "second", Policy::default);
+        assert!(!policy.stop().stop_execution(&ctx));
+        let first = FIRST.get_or_insert_with(&registry, "test", "first", || unreachable!());
+        assert!(Arc::ptr_eq(&first, &policy));
+    }
+
+    #[test]
+    fn test_policy_retry() {
+        let policy = Policy::new(
+            Box::new(StopAttempts::new(3)),
+            Box::new(WaitFixed::new(Duration::ZERO)),
+        );
+
+        let mut calls = 0;
+        let result = policy.retry(|| {
+            calls += 1;
+            if calls == 2 {
+                Ok(calls)
+            } else {
+                Err(calls)
+            }
+        });
+        assert_eq!(result, Ok(2));
+
+        let mut calls = 0;
+        let result = policy.retry(|| {
+            calls += 1;
+            None::<i32>
+        });
+        assert_eq!(result, None);
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn test_policy_display_and_schedule() {
+        let policy = Policy::from_strategies(
+            StopStrategy {
+                attempts: Some(3),
+                duration: Some(Duration::from_secs(60)),
+            },
+            Some(WaitStrategy::Fixed {
+                duration: Duration::from_millis(1500),
+            }),
+        );
+
+        assert_eq!(
+            policy.to_string(),
+            "stops after 3 attempts or 60s, waits 1.5s between attempts"
+        );
+        assert_eq!(
+            format!("{:?}", policy),
+            "Policy { stop: stops after 3 attempts or 60s, wait: waits 1.5s }"
+        );
+        assert_eq!(
+            policy
+                .schedule()
+                .gives_up_after()
+                .map(|after| after.expected),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(policy.simulate(2).attempts.len(), 2);
+        assert!(!policy.simulate(2).stops);
+
+        let policy = Policy::from_strategies(
+            StopStrategy {
+                attempts: None,
+                duration: None,
+            },
+            Some(WaitStrategy::Exponential {
+                multiplier: Duration::from_millis(100),
+                min: Duration::ZERO,
+                max: Duration::from_secs(10),
+                exp_base: 2,
+            }),
+        );
+        assert_eq!(
+            policy.to_string(),
+            "never stops, waits 100ms * 2^(attempt - 1) + 0s, at most 10s between attempts"
+        );
+        assert_eq!(
+            policy.schedule().attempts.len(),
+            Schedule::MAX_ATTEMPTS as usize
+        );
+    }
+
+    #[test]
+    fn test_policy_retry_with_abort() {
+        let policy = Policy::new(
+            Box::new(StopAttempts::new(3)),
+            Box::new(WaitFixed::new(Duration::from_millis(1))),
+        );
+        let abort = RetryAbort::new();
+
+        let mut calls = 0;
+        let result = policy.retry_with_abort(&abort, || {
+            calls += 1;
+            Err::<i32, i32>(calls)
+        });
+        assert_eq!(result, Err(3));
+
+        let policy = Policy::new(
+            Box::new(StopAttempts::new(3)),
+            Box::new(WaitFixed::new(Duration::from_secs(60))),
+        );
+        let result = std::thread::scope(|scope| {
+            let retries = scope.spawn(|| {
+                let mut calls = 0;
+                policy.retry_with_abort(&abort, || {
+                    calls += 1;
+                    Err::<i32, i32>(calls)
+                })
+            });
+            std::thread::sleep(Duration::from_millis(20));
+            abort.abort();
+            retries.join().unwrap()
+        });
+        assert_eq!(result, Err(1));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_policy_retry_async() {
+        let policy = Policy::new(
+            Box::new(StopAttempts::new(3)),
+            Box::new(WaitFixed::new(Duration::from_millis(1))),
+        );
+
+        let mut calls = 0;
+        let result = policy
+            .retry_async(|| {
+                calls += 1;
+                let result: Result<i32, i32> = Err(calls);
+                async move { result }
+            })
+            .await;
+        assert_eq!(result, Err(3));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_policy_retry_async_with_cancel() {
+        let policy = Policy::new(
+            Box::new(StopAttempts::new(3)),
+            Box::new(WaitFixed::new(Duration::from_millis(1))),
+        );
+
+        let result = policy
+            .retry_async_with_cancel(std::future::pending::<()>(), || async {
+                Err::<i32, i32>(1)
+            })
+            .await;
+        assert_eq!(result, Ok(Err(1)));
+
+        let policy = Policy::new(
+            Box::new(StopAttempts::new(3)),
+            Box::new(WaitFixed::new(Duration::from_secs(60))),
+        );
+        let mut calls = 0;
+        let result = policy
+            .retry_async_with_cancel(tokio::time::sleep(Duration::from_millis(10)), || {
+                calls += 1;
+                async { Err::<i32, i32>(1) }
+            })
+            .await;
+        assert_eq!(result, Err(Cancelled));
+        assert_eq!(calls, 1);
+    }
+}
@@ -0,0 +1,146 @@
+/// Outcome of a single attempt.
+#[derive(Debug, PartialEq)]
+pub enum Outcome<'a, E> {
+    /// attempt is successful and its result is returned to the caller.
+    Success,
+    /// attempt failed with error that can be retried.
+    Retry(&'a E),
+    /// attempt failed with error that should not be retried, so its result is returned to the caller immediately.
+    Abort(&'a E),
+}
+
+/// Result-like type that can be retried by `retry` macros and [`Policy::retry`](crate::Policy::retry).
+///
+/// Implemented for `Result` (retry on `Err`) and `Option` (retry on `None`).
+/// Custom types (for example, RPC response with status) can implement it to be used as return type of functions with `retry` macros.
+pub trait Retryable {
+    type Ok;
+    type Error;
+
+    /// classifies result of the attempt.
+    fn outcome(&self) -> Outcome<'_, Self::Error>;
+
+    fn is_success(&self) -> bool {
+        matches!(self.outcome(), Outcome::Success)
+    }
+
+    fn into_result(self) -> Result<Self::Ok, Self::Error>
+    where
+        Self: Sized;
+}
+
+impl<T, E> Retryable for Result<T, E> {
+    type Ok = T;
+    type Error = E;
+
+    fn outcome(&self) -> Outcome<'_, E> {
+        match self {
+            Ok(_) => Outcome::Success,
+            Err(err) => Outcome::Retry(err),
+        }
+    }
+
+    fn into_result(self) -> Result<T, E> {
+        self
+    }
+}
+
+impl<T> Retryable for Option<T> {
+    type Ok = T;
+    type Error = ();
+
+    fn outcome(&self) -> Outcome<'_, ()> {
+        match self {
+            Some(_) => Outcome::Success,
+            None => Outcome::Retry(&()),
+        }
+    }
+
+    fn into_result(self) -> Result<T, ()> {
+        self.ok_or(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    enum Status {
+        Ok,
+        Unavailable,
+        NotFound,
+    }
+
+    struct Response {
+        status: Status,
+        body: &'static str,
+    }
+
+    impl Retryable for Response {
+        type Ok = &'static str;
+        type Error = Status;
+
+        fn outcome(&self) -> Outcome<'_, Status> {
+            match self.status {
+                Status::Ok => Outcome::Success,
+                Status::Unavailable => Outcome::Retry(&self.status),
+                Status::NotFound => Outcome::Abort(&self.status),
+            }
+        }
+
+        fn into_result(self) -> Result<&'static str, Status> {
+            match self.status {
+                Status::Ok => Ok(self.body),
+                status => Err(status),
+            }
+        }
+    }
+
+    #[test]
+    fn test_result_retryable() {
+        let ok: Result<i32, &str> = Ok(1);
+        let err: Result<i32, &str> = Err("error");
+
+        assert_eq!(ok.outcome(), Outcome::Success);
+        assert_eq!(err.outcome(), Outcome::Retry(&"error"));
+        assert!(ok.is_success());
+        assert!(!err.is_success());
+        assert_eq!(err.into_result(), Err("error"));
+    }
+
+    #[test]
+    fn test_option_retryable() {
+        assert_eq!(Some(1).outcome(), Outcome::Success);
+        assert_eq!(None::<i32>.outcome(), Outcome::Retry(&()));
+        assert_eq!(Some(1).into_result(), Ok(1));
+        assert_eq!(None::<i32>.into_result(), Err(()));
+    }
+
+    #[test]
+    fn test_custom_retryable() {
+        let response = Response {
+            status: Status::NotFound,
+            body: "",
+        };
+        assert!(matches!(
+            response.outcome(),
+            Outcome::Abort(Status::NotFound)
+        ));
+
+        let response = Response {
+            status: Status::Unavailable,
+            body: "",
+        };
+        assert!(matches!(
+            response.outcome(),
+            Outcome::Retry(Status::Unavailable)
+        ));
+
+        let response = Response {
+            status: Status::Ok,
+            body: "body",
+        };
+        assert!(response.is_success());
+        assert!(matches!(response.into_result(), Ok("body")));
+    }
+}
@@ -0,0 +1,245 @@
+//! Preview of planned attempts and waits of retry policies.
+//!
+//! The schedule is simulated without sleeping and assumes that attempts take no time, so duration stop strategies
+//! are checked against cumulative waits. Random waits are described by range and expected (mean) value,
+//! the number of planned attempts is based on expected values.
+
+use crate::stop::Stop;
+use crate::wait::Wait;
+use crate::{Duration, RetryingContext};
+use std::fmt;
+use std::ops::Add;
+
+/// Range of durations of random strategies. For deterministic strategies `min`, `expected` and `max` are equal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DurationRange {
+    pub min: Duration,
+    pub expected: Duration,
+    pub max: Duration,
+}
+
+impl DurationRange {
+    pub const ZERO: DurationRange = DurationRange::exact(Duration::ZERO);
+
+    pub const fn exact(duration: Duration) -> DurationRange {
+        DurationRange {
+            min: duration,
+            expected: duration,
+            max: duration,
+        }
+    }
+
+    pub fn is_exact(&self) -> bool {
+        self.min == self.max
+    }
+}
+
+impl Add for DurationRange {
+    type Output = DurationRange;
+
+    fn add(self, other: DurationRange) -> DurationRange {
+        DurationRange {
+            min: self.min.saturating_add(other.min),
+            expected: self.expected.saturating_add(other.expected),
+            max: self.max.saturating_add(other.max),
+        }
+    }
+}
+
+impl fmt::Display for DurationRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_exact() {
+            write!(f, "{}", DisplayDuration(self.expected))
+        } else {
+            write!(
+                f,
+                "{}..{} (expected {})",
+                DisplayDuration(self.min),
+                DisplayDuration(self.max),
+                DisplayDuration(self.expected)
+            )
+        }
+    }
+}
+
+/// Planned attempt of retry loop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlannedAttempt {
+    /// number of the attempt starting from 1.
+    pub attempt: u32,
+    /// wait before the attempt.
+    pub wait: DurationRange,
+    /// time from the beginning of the first attempt.
+    pub at: DurationRange,
+}
+
+/// Planned attempts of retry loop returned by [`simulate`] and [`Policy::schedule`](crate::Policy::schedule).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    pub attempts: Vec<PlannedAttempt>,
+    /// `true` if the stop strategy stops retrying after the last planned attempt, `false` if simulation was
+    /// limited by number of attempts.
+    pub stops: bool,
+}
+
+impl Schedule {
+    /// maximal number of attempts simulated by [`Policy::schedule`](crate::Policy::schedule).
+    pub const MAX_ATTEMPTS: u32 = 1000;
+
+    /// returns time after which retrying gives up or `None` if it doesn't stop during simulated attempts.
+    pub fn gives_up_after(&self) -> Option<DurationRange> {
+        if self.stops {
+            self.attempts.last().map(|attempt| attempt.at)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for attempt in &self.attempts {
+            write!(f, "attempt {} at {}", attempt.attempt, attempt.at)?;
+            if attempt.attempt > 1 {
+                write!(f, " after wait {}", attempt.wait)?;
+            }
+            writeln!(f)?;
+        }
+        match self.gives_up_after() {
+            Some(after) => write!(f, "gives up after {}", after),
+            None => write!(f, "keeps retrying after {} attempts", self.attempts.len()),
+        }
+    }
+}
+
+/// returns at most `attempts` planned attempts of retry loop with `stop` and `wait` strategies.
+pub fn simulate(stop: &dyn Stop, wait: &dyn Wait, attempts: u32) -> Schedule {
+    let mut at = DurationRange::ZERO;
+    let mut planned = vec![PlannedAttempt {
+        attempt: 1,
+        wait: DurationRange::ZERO,
+        at,
+    }];
+    let mut attempt = 1;
+    loop {
+        if stop.stop_execution(&RetryingContext::simulated(attempt, at.expected)) {
+            return Schedule {
+                attempts: planned,
+                stops: true,
+            };
+        }
+        if attempt >= attempts {
+            return Schedule {
+                attempts: planned,
+                stops: false,
+            };
+        }
+        attempt += 1;
+        let wait = wait.wait_range(&RetryingContext::simulated(attempt, at.expected));
+        at = at + wait;
+        planned.push(PlannedAttempt { attempt, wait, at });
+    }
+}
+
+/// formats zero duration as `0s` instead of `0ns`.
+pub(crate) struct DisplayDuration(pub(crate) Duration);
+
+impl fmt::Display for DisplayDuration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_zero() {
+            write!(f, "0s")
+        } else {
+            write!(f, "{:?}", self.0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stop::{StopAttempts, StopAttemptsOrDuration, StopNever};
+    use crate::wait::{WaitExponential, WaitFixed, WaitRandom};
+
+    #[test]
+    fn test_simulate_fixed() {
+        let schedule = simulate(
+            &StopAttempts::new(3),
+            &WaitFixed::new(Duration::from_millis(1500)),
+            10,
+        );
+
+        assert!(schedule.stops);
+        assert_eq!(schedule.attempts.len(), 3);
+        assert_eq!(
+            schedule.attempts[2],
+            PlannedAttempt {
+                attempt: 3,
+                wait: DurationRange::exact(Duration::from_millis(1500)),
+                at: DurationRange::exact(Duration::from_secs(3)),
+            }
+        );
+        assert_eq!(
+            schedule.gives_up_after(),
+            Some(DurationRange::exact(Duration::from_secs(3)))
+        );
+        assert_eq!(
+            schedule.to_string(),
+            "attempt 1 at 0s\n\
+             attempt 2 at 1.5s after wait 1.5s\n\
+             attempt 3 at 3s after wait 1.5s\n\
+             gives up after 3s"
+        );
+    }
+
+    #[test]
+    fn test_simulate_exponential_with_duration() {
+        let schedule = simulate(
+            &StopAttemptsOrDuration::new(10, Duration::from_secs(10)),
+            &WaitExponential::new(
+                Duration::from_secs(1),
+                Duration::ZERO,
+                Duration::from_secs(60),
+                2,
+            ),
+            100,
+        );
+
+        let at: Vec<_> = schedule
+            .attempts
+            .iter()
+            .map(|attempt| attempt.at.expected.as_secs())
+            .collect();
+        assert_eq!(at, vec![0, 2, 6, 14]);
+        assert!(schedule.stops);
+    }
+
+    #[test]
+    fn test_simulate_random() {
+        let schedule = simulate(
+            &StopAttempts::new(2),
+            &WaitRandom::new(Duration::from_secs(1), Duration::from_secs(3)),
+            10,
+        );
+
+        let range = DurationRange {
+            min: Duration::from_secs(1),
+            expected: Duration::from_secs(2),
+            max: Duration::from_secs(3),
+        };
+        assert_eq!(schedule.attempts[1].wait, range);
+        assert_eq!(schedule.gives_up_after(), Some(range));
+        assert_eq!(range.to_string(), "1s..3s (expected 2s)");
+    }
+
+    #[test]
+    fn test_simulate_never_stop() {
+        let schedule = simulate(&StopNever {}, &WaitFixed::new(Duration::ZERO), 5);
+
+        assert!(!schedule.stops);
+        assert_eq!(schedule.attempts.len(), 5);
+        assert_eq!(schedule.gives_up_after(), None);
+        assert!(schedule
+            .to_string()
+            .ends_with("keeps retrying after 5 attempts"));
+    }
+}
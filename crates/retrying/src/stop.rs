@@ -1,7 +1,14 @@
-use crate::RetryingContext;
+use crate::schedule::DisplayDuration;
+use crate::{Duration, RetryingContext, RetryingError};
+use std::fmt;
 
 pub trait Stop {
     fn stop_execution(&self, ctx: &RetryingContext) -> bool;
+
+    /// describes the strategy in plain words (for example, `stops after 3 attempts`).
+    fn describe(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "stops by custom condition")
+    }
 }
 
 pub struct StopAttempts {
@@ -18,14 +25,21 @@ impl Stop for StopAttempts {
     fn stop_execution(&self, ctx: &RetryingContext) -> bool {
         ctx.attempt_num >= self.attempts
     }
+
+    fn describe(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.attempts {
+            1 => write!(f, "stops after 1 attempt"),
+            attempts => write!(f, "stops after {} attempts", attempts),
+        }
+    }
 }
 
 pub struct StopDuration {
-    duration: f32,
+    duration: Duration,
 }
 
 impl StopDuration {
-    pub fn new(duration: f32) -> StopDuration {
+    pub fn new(duration: Duration) -> StopDuration {
         StopDuration { duration }
     }
 }
@@ -34,10 +48,13 @@ impl Stop for StopDuration {
     fn stop_execution(&self, ctx: &RetryingContext) -> bool {
         ::std::time::SystemTime::now()
             .duration_since(ctx.started_at())
-            .unwrap()
-            .as_secs_f32()
+            .unwrap_or_default()
             >= self.duration
     }
+
+    fn describe(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "stops after {}", DisplayDuration(self.duration))
+    }
 }
 
 pub struct StopAttemptsOrDuration {
@@ -46,7 +63,7 @@ pub struct StopAttemptsOrDuration {
 }
 
 impl StopAttemptsOrDuration {
-    pub fn new(attempts: u32, duration: f32) -> StopAttemptsOrDuration {
+    pub fn new(attempts: u32, duration: Duration) -> StopAttemptsOrDuration {
         StopAttemptsOrDuration {
             attempts: StopAttempts { attempts },
             duration: StopDuration { duration },
@@ -58,6 +75,109 @@ impl Stop for StopAttemptsOrDuration {
     fn stop_execution(&self, ctx: &RetryingContext) -> bool {
         self.attempts.stop_execution(ctx) || self.duration.stop_execution(ctx)
     }
+
+    fn describe(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.attempts.describe(f)?;
+        write!(f, " or {}", DisplayDuration(self.duration.duration))
+    }
+}
+
+pub struct StopNever {}
+
+impl Stop for StopNever {
+    fn stop_execution(&self, _ctx: &RetryingContext) -> bool {
+        false
+    }
+
+    fn describe(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "never stops")
+    }
+}
+
+/// Stop strategy that stops when `predicate` returns `true` (for example, on global shutdown flag or feature toggle).
+pub struct StopFn<F> {
+    predicate: F,
+}
+
+impl<F: Fn(&RetryingContext) -> bool> StopFn<F> {
+    pub fn new(predicate: F) -> StopFn<F> {
+        StopFn { predicate }
+    }
+}
+
+impl<F: Fn(&RetryingContext) -> bool> Stop for StopFn<F> {
+    fn stop_execution(&self, ctx: &RetryingContext) -> bool {
+        (self.predicate)(ctx)
+    }
+
+    fn describe(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "stops when predicate returns true")
+    }
+}
+
+/// Stop strategy that stops when any of two strategies stops.
+pub struct StopOr<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A: Stop, B: Stop> StopOr<A, B> {
+    pub fn new(first: A, second: B) -> StopOr<A, B> {
+        StopOr { first, second }
+    }
+}
+
+impl<A: Stop, B: Stop> Stop for StopOr<A, B> {
+    fn stop_execution(&self, ctx: &RetryingContext) -> bool {
+        self.first.stop_execution(ctx) || self.second.stop_execution(ctx)
+    }
+
+    fn describe(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.first.describe(f)?;
+        write!(f, " or ")?;
+        self.second.describe(f)
+    }
+}
+
+impl<S: Stop + ?Sized> Stop for Box<S> {
+    fn stop_execution(&self, ctx: &RetryingContext) -> bool {
+        (**self).stop_execution(ctx)
+    }
+
+    fn describe(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        (**self).describe(f)
+    }
+}
+
+/// Description of stop strategy that can be built in runtime (for example, from OS environment variables).
+/// Both `attempts` and `duration` are optional; strategy without any of them never stops.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StopStrategy {
+    pub attempts: Option<u32>,
+    pub duration: Option<Duration>,
+}
+
+impl StopStrategy {
+    /// checks that strategy values are in range.
+    pub fn validate(&self) -> Result<(), RetryingError> {
+        match self.attempts {
+            Some(0) => Err(RetryingError::new(
+                "stop attempts should be positive number",
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn build(self) -> Box<dyn Stop + Send + Sync> {
+        match (self.attempts, self.duration) {
+            (Some(attempts), None) => Box::new(StopAttempts::new(attempts)),
+            (None, Some(duration)) => Box::new(StopDuration::new(duration)),
+            (Some(attempts), Some(duration)) => {
+                Box::new(StopAttemptsOrDuration::new(attempts, duration))
+            }
+            (None, None) => Box::new(StopNever {}),
+        }
+    }
 }
 
 mod tests {
@@ -82,9 +202,104 @@ mod tests {
         use crate::*;
 
         let ctx = RetryingContext::default();
-        let stop = StopDuration { duration: 0.1f32 };
+        let stop = StopDuration {
+            duration: Duration::from_millis(100),
+        };
+
+        sleep_sync(Duration::from_millis(200));
+        assert!(stop.stop_execution(&ctx));
+    }
+
+    #[test]
+    fn test_never_stop_execution() {
+        use super::*;
+
+        let stop = StopNever {};
+        let mut ctx = RetryingContext::default();
+
+        for _ in 0..100 {
+            ctx.add_attempt();
+        }
+        assert!(!stop.stop_execution(&ctx));
+    }
+
+    #[test]
+    fn test_fn_stop_execution() {
+        use super::*;
 
-        sleep_sync(Duration::from_secs_f32(0.2f32));
+        let stop = StopFn::new(|ctx: &RetryingContext| ctx.attempt_num() >= 3);
+        let mut ctx = RetryingContext::default();
+
+        assert!(!stop.stop_execution(&ctx));
+
+        ctx.add_attempt();
+        ctx.add_attempt();
         assert!(stop.stop_execution(&ctx));
+
+        let policy = crate::Policy::new(
+            Box::new(stop),
+            Box::new(crate::wait::WaitFixed::new(Duration::from_secs(1))),
+        );
+        assert_eq!(
+            policy.to_string(),
+            "stops when predicate returns true, waits 1s between attempts"
+        );
+    }
+
+    #[test]
+    fn test_or_stop_execution() {
+        use super::*;
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        static SHUTDOWN: AtomicBool = AtomicBool::new(false);
+        let stop = StopOr::new(
+            StopAttempts::new(3),
+            StopFn::new(|_ctx: &RetryingContext| SHUTDOWN.load(Ordering::SeqCst)),
+        );
+        let mut ctx = RetryingContext::default();
+
+        assert!(!stop.stop_execution(&ctx));
+
+        SHUTDOWN.store(true, Ordering::SeqCst);
+        assert!(stop.stop_execution(&ctx));
+
+        SHUTDOWN.store(false, Ordering::SeqCst);
+        ctx.add_attempt();
+        ctx.add_attempt();
+        assert!(stop.stop_execution(&ctx));
+    }
+
+    #[test]
+    fn test_stop_strategy_build() {
+        use super::*;
+
+        let stop = StopStrategy {
+            attempts: Some(2),
+            duration: Some(Duration::from_secs(3600)),
+        }
+        .build();
+        let mut ctx = RetryingContext::default();
+
+        assert!(!stop.stop_execution(&ctx));
+
+        ctx.add_attempt();
+        assert!(stop.stop_execution(&ctx));
+    }
+
+    #[test]
+    fn test_stop_strategy_validate() {
+        use super::*;
+
+        let stop = StopStrategy {
+            attempts: Some(2),
+            duration: Some(Duration::from_millis(500)),
+        };
+        assert!(stop.validate().is_ok());
+
+        let stop = StopStrategy {
+            attempts: Some(0),
+            duration: None,
+        };
+        assert!(stop.validate().is_err());
     }
 }
@@ -0,0 +1,279 @@
+//! Stream adapters that retry failed items (available with `stream` feature and `tokio` or `async_std` feature).
+
+use crate::{Policy, Retryable, RetryingContext};
+use futures_core::Stream;
+use std::borrow::Borrow;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+type Sleep = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+fn sleep(policy: &Policy, ctx: &mut RetryingContext) -> Sleep {
+    ctx.add_attempt();
+    let wait = policy.wait().wait_duration(ctx);
+    ctx.add_wait(wait);
+    Box::pin(crate::sleep_async(wait))
+}
+
+/// Extension of streams with retrying adapters.
+pub trait StreamExt: Stream + Sized {
+    /// returns stream that calls `operation` for every item and retries the returned future using `policy`.
+    /// Result of the last attempt for every item is returned by the stream.
+    fn retry_each<P, F, Fut>(self, policy: P, operation: F) -> RetryEach<Self, P, F, Fut>
+    where
+        P: Borrow<Policy>,
+        F: FnMut(&Self::Item) -> Fut,
+        Fut: Future,
+        Fut::Output: Retryable,
+    {
+        RetryEach {
+            stream: Box::pin(self),
+            policy,
+            operation,
+            state: EachState::Next,
+            ctx: RetryingContext::new(),
+        }
+    }
+}
+
+impl<S: Stream> StreamExt for S {}
+
+enum EachState<T, Fut> {
+    Next,
+    Attempt { item: T, future: Pin<Box<Fut>> },
+    Sleep { item: T, sleep: Sleep },
+}
+
+/// Stream returned by [`StreamExt::retry_each`].
+pub struct RetryEach<S: Stream, P, F, Fut> {
+    stream: Pin<Box<S>>,
+    policy: P,
+    operation: F,
+    state: EachState<S::Item, Fut>,
+    ctx: RetryingContext,
+}
+
+// fields are never pinned (stream and futures are boxed), so the stream can be moved
+impl<S: Stream, P, F, Fut> Unpin for RetryEach<S, P, F, Fut> {}
+
+impl<S, P, F, Fut> Stream for RetryEach<S, P, F, Fut>
+where
+    S: Stream,
+    P: Borrow<Policy>,
+    F: FnMut(&S::Item) -> Fut,
+    Fut: Future,
+    Fut::Output: Retryable,
+{
+    type Item = Fut::Output;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Fut::Output>> {
+        let this = self.get_mut();
+        loop {
+            match &mut this.state {
+                EachState::Next => match ready!(this.stream.as_mut().poll_next(cx)) {
+                    None => return Poll::Ready(None),
+                    Some(item) => {
+                        this.ctx = RetryingContext::new();
+                        let future = Box::pin((this.operation)(&item));
+                        this.state = EachState::Attempt { item, future };
+                    }
+                },
+                EachState::Attempt { future, .. } => {
+                    let result = ready!(future.as_mut().poll(cx));
+                    let policy = this.policy.borrow();
+                    let state = std::mem::replace(&mut this.state, EachState::Next);
+                    if !policy.should_retry(&result, &this.ctx) {
+                        return Poll::Ready(Some(result));
+                    }
+                    if let EachState::Attempt { item, .. } = state {
+                        let sleep = sleep(policy, &mut this.ctx);
+                        this.state = EachState::Sleep { item, sleep };
+                    }
+                }
+                EachState::Sleep { sleep, .. } => {
+                    ready!(sleep.as_mut().poll(cx));
+                    if let EachState::Sleep { item, .. } =
+                        std::mem::replace(&mut this.state, EachState::Next)
+                    {
+                        let future = Box::pin((this.operation)(&item));
+                        this.state = EachState::Attempt { item, future };
+                    }
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let pending = usize::from(!matches!(self.state, EachState::Next));
+        let (lower, upper) = self.stream.size_hint();
+        (
+            lower.saturating_add(pending),
+            upper.and_then(|upper| upper.checked_add(pending)),
+        )
+    }
+}
+
+/// returns stream over items of stream created by `factory`. When the stream returns error, it is re-created by
+/// `factory` using `policy` with position of the last successful item (`None` if there is no such item) that is
+/// returned by `position`, so the new stream can resume from it.
+///
+/// Stop and wait strategies of `policy` are applied to consecutive errors, so the context is reset after every
+/// successful item. The error is returned and the stream ends when `policy` stops retrying.
+pub fn retry_stream<P, S, T, E, K, G, F>(
+    policy: P,
+    position: G,
+    factory: F,
+) -> RetryStream<P, S, K, G, F>
+where
+    P: Borrow<Policy>,
+    S: Stream<Item = Result<T, E>>,
+    G: FnMut(&T) -> K,
+    F: FnMut(Option<&K>) -> S,
+{
+    RetryStream {
+        policy,
+        position,
+        factory,
+        stream: None,
+        sleep: None,
+        last: None,
+        ctx: RetryingContext::new(),
+        done: false,
+    }
+}
+
+/// Stream returned by [`retry_stream`].
+pub struct RetryStream<P, S, K, G, F> {
+    policy: P,
+    position: G,
+    factory: F,
+    stream: Option<Pin<Box<S>>>,
+    sleep: Option<Sleep>,
+    last: Option<K>,
+    ctx: RetryingContext,
+    done: bool,
+}
+
+// fields are never pinned (stream and sleep are boxed), so the stream can be moved
+impl<P, S, K, G, F> Unpin for RetryStream<P, S, K, G, F> {}
+
+impl<P, S, T, E, K, G, F> Stream for RetryStream<P, S, K, G, F>
+where
+    P: Borrow<Policy>,
+    S: Stream<Item = Result<T, E>>,
+    G: FnMut(&T) -> K,
+    F: FnMut(Option<&K>) -> S,
+{
+    type Item = Result<T, E>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<T, E>>> {
+        let this = self.get_mut();
+        while !this.done {
+            if let Some(sleep) = &mut this.sleep {
+                ready!(sleep.as_mut().poll(cx));
+                this.sleep = None;
+            }
+            let factory = &mut this.factory;
+            let last = this.last.as_ref();
+            let stream = this.stream.get_or_insert_with(|| Box::pin(factory(last)));
+            match ready!(stream.as_mut().poll_next(cx)) {
+                None => this.done = true,
+                Some(Ok(item)) => {
+                    this.last = Some((this.position)(&item));
+                    this.ctx = RetryingContext::new();
+                    return Poll::Ready(Some(Ok(item)));
+                }
+                Some(Err(err)) => {
+                    let result = Err(err);
+                    let policy = this.policy.borrow();
+                    if !policy.should_retry(&result, &this.ctx) {
+                        this.done = true;
+                        return Poll::Ready(Some(result));
+                    }
+                    this.stream = None;
+                    this.sleep = Some(sleep(policy, &mut this.ctx));
+                }
+            }
+        }
+        Poll::Ready(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stop::StopAttempts;
+    use crate::wait::WaitFixed;
+    use crate::Duration;
+    use futures::StreamExt as _;
+
+    fn policy(attempts: u32) -> Policy {
+        Policy::new(
+            Box::new(StopAttempts::new(attempts)),
+            Box::new(WaitFixed::new(Duration::from_millis(1))),
+        )
+    }
+
+    fn failing_stream(start: u32, fail_at: u32) -> impl Stream<Item = Result<u32, String>> {
+        futures::stream::iter((start..=5).map(move |item| {
+            if item == fail_at {
+                Err(format!("failed at {}", item))
+            } else {
+                Ok(item)
+            }
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_retry_each() {
+        let mut calls = 0;
+        let results: Vec<Result<u32, u32>> = futures::stream::iter([1, 2, 3])
+            .retry_each(policy(2), |item| {
+                calls += 1;
+                let item = *item;
+                async move {
+                    if item == 2 {
+                        Err(item)
+                    } else {
+                        Ok(item * 10)
+                    }
+                }
+            })
+            .collect()
+            .await;
+
+        assert_eq!(results, vec![Ok(10), Err(2), Ok(30)]);
+        assert_eq!(calls, 4);
+    }
+
+    #[tokio::test]
+    async fn test_retry_stream() {
+        let policy = policy(2);
+
+        let mut fail_at = vec![4, 2];
+        let mut starts = vec![];
+        let items: Vec<_> = retry_stream(
+            &policy,
+            |item: &u32| *item,
+            |last| {
+                let start = last.map_or(1, |last| last + 1);
+                starts.push(start);
+                failing_stream(start, fail_at.pop().unwrap_or(u32::MAX))
+            },
+        )
+        .collect()
+        .await;
+        assert_eq!(items, vec![Ok(1), Ok(2), Ok(3), Ok(4), Ok(5)]);
+        assert_eq!(starts, vec![1, 2, 4]);
+
+        let items: Vec<_> = retry_stream(
+            &policy,
+            |item: &u32| *item,
+            |last| failing_stream(last.map_or(1, |last| last + 1), 2),
+        )
+        .collect()
+        .await;
+        assert_eq!(items, vec![Ok(1), Err(String::from("failed at 2"))]);
+    }
+}
@@ -1,57 +1,95 @@
-use crate::RetryingContext;
+use crate::schedule::{DisplayDuration, DurationRange};
+use crate::{Duration, RetryingContext, RetryingError};
+use std::fmt;
 
 pub trait Wait {
-    fn wait_seconds(&self, ctx: &RetryingContext) -> f32;
+    fn wait_duration(&self, ctx: &RetryingContext) -> Duration;
 
-    fn wait_duration(&self, ctx: &RetryingContext) -> crate::Duration {
-        crate::Duration::from_secs_f32(self.wait_seconds(ctx))
+    fn wait_seconds(&self, ctx: &RetryingContext) -> f32 {
+        self.wait_duration(ctx).as_secs_f32()
+    }
+
+    /// returns range of possible waits, random strategies should override it.
+    fn wait_range(&self, ctx: &RetryingContext) -> DurationRange {
+        DurationRange::exact(self.wait_duration(ctx))
+    }
+
+    /// describes the strategy in plain words (for example, `waits 1.5s`).
+    fn describe(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "waits by custom strategy")
     }
 }
 
 pub struct WaitFixed {
-    seconds: f32,
+    duration: Duration,
 }
 
 impl WaitFixed {
-    pub fn new(seconds: f32) -> WaitFixed {
-        WaitFixed { seconds }
+    pub fn new(duration: Duration) -> WaitFixed {
+        WaitFixed { duration }
     }
 }
 
 impl Wait for WaitFixed {
-    fn wait_seconds(&self, _ctx: &RetryingContext) -> f32 {
-        self.seconds
+    fn wait_duration(&self, _ctx: &RetryingContext) -> Duration {
+        self.duration
+    }
+
+    fn describe(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "waits {}", DisplayDuration(self.duration))
     }
 }
 
 pub struct WaitRandom {
-    min: f32,
-    max: f32,
+    min: Duration,
+    max: Duration,
 }
 
 impl WaitRandom {
-    pub fn new(min: f32, max: f32) -> WaitRandom {
+    pub fn new(min: Duration, max: Duration) -> WaitRandom {
         WaitRandom { min, max }
     }
 }
 
 impl Wait for WaitRandom {
-    fn wait_seconds(&self, _ctx: &RetryingContext) -> f32 {
+    fn wait_duration(&self, _ctx: &RetryingContext) -> Duration {
         use crate::rand::Rng;
         let mut random_rng = crate::rand::thread_rng();
-        random_rng.gen_range(self.min..=self.max) as f32
+        random_rng.gen_range(self.min..=self.max)
+    }
+
+    fn wait_range(&self, _ctx: &RetryingContext) -> DurationRange {
+        DurationRange {
+            min: self.min,
+            expected: self.min + (self.max.saturating_sub(self.min)) / 2,
+            max: self.max,
+        }
+    }
+
+    fn describe(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "waits random {}..{}",
+            DisplayDuration(self.min),
+            DisplayDuration(self.max)
+        )
     }
 }
 
 pub struct WaitExponential {
-    multiplier: f32,
-    min: f32,
-    max: f32,
+    multiplier: Duration,
+    min: Duration,
+    max: Duration,
     exp_base: u32,
 }
 
 impl WaitExponential {
-    pub fn new(multiplier: f32, min: f32, max: f32, exp_base: u32) -> WaitExponential {
+    pub fn new(
+        multiplier: Duration,
+        min: Duration,
+        max: Duration,
+        exp_base: u32,
+    ) -> WaitExponential {
         WaitExponential {
             multiplier,
             min,
@@ -62,9 +100,121 @@ impl WaitExponential {
 }
 
 impl Wait for WaitExponential {
-    fn wait_seconds(&self, ctx: &RetryingContext) -> f32 {
-        self.max
-            .min(self.multiplier * (self.exp_base.pow(ctx.attempt_num - 1) as f32) + self.min)
+    fn wait_duration(&self, ctx: &RetryingContext) -> Duration {
+        self.exp_base
+            .checked_pow(ctx.attempt_num - 1)
+            .and_then(|factor| self.multiplier.checked_mul(factor))
+            .and_then(|wait| wait.checked_add(self.min))
+            .map_or(self.max, |wait| wait.min(self.max))
+    }
+
+    fn describe(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "waits {} * {}^(attempt - 1) + {}, at most {}",
+            DisplayDuration(self.multiplier),
+            self.exp_base,
+            DisplayDuration(self.min),
+            DisplayDuration(self.max)
+        )
+    }
+}
+
+/// Description of wait strategy that can be built in runtime (for example, from OS environment variables).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WaitStrategy {
+    Fixed {
+        duration: Duration,
+    },
+    Random {
+        min: Duration,
+        max: Duration,
+    },
+    Exponential {
+        multiplier: Duration,
+        min: Duration,
+        max: Duration,
+        exp_base: u32,
+    },
+}
+
+impl WaitStrategy {
+    pub const FIXED: &'static str = "fixed";
+    pub const RANDOM: &'static str = "random";
+    pub const EXPONENTIAL: &'static str = "exponential";
+
+    /// returns strategy with default values by its name (`fixed`, `random` or `exponential`, case-insensitive).
+    pub fn from_name(name: &str) -> Option<WaitStrategy> {
+        match name.trim().to_lowercase().as_str() {
+            Self::FIXED => Some(WaitStrategy::Fixed {
+                duration: Duration::ZERO,
+            }),
+            Self::RANDOM => Some(WaitStrategy::Random {
+                min: Duration::ZERO,
+                max: Duration::from_secs(3600),
+            }),
+            Self::EXPONENTIAL => Some(WaitStrategy::Exponential {
+                multiplier: Duration::from_secs(1),
+                min: Duration::ZERO,
+                max: Duration::from_secs(3600),
+                exp_base: 2,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            WaitStrategy::Fixed { .. } => Self::FIXED,
+            WaitStrategy::Random { .. } => Self::RANDOM,
+            WaitStrategy::Exponential { .. } => Self::EXPONENTIAL,
+        }
+    }
+
+    /// checks that strategy values are in range (for example, `min` is not greater than `max`).
+    pub fn validate(&self) -> Result<(), RetryingError> {
+        let check_min_max = |min: Duration, max: Duration| {
+            if min <= max {
+                Ok(())
+            } else {
+                Err(RetryingError::new(&format!(
+                    "{} wait `min` ({:?}) should not be greater than `max` ({:?})",
+                    self.name(),
+                    min,
+                    max
+                )))
+            }
+        };
+
+        match *self {
+            WaitStrategy::Fixed { .. } => Ok(()),
+            WaitStrategy::Random { min, max } => check_min_max(min, max),
+            WaitStrategy::Exponential {
+                min, max, exp_base, ..
+            } => {
+                check_min_max(min, max)?;
+                if exp_base == 0 {
+                    Err(RetryingError::new(
+                        "exponential wait `exp_base` should be positive",
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    pub fn build(self) -> Box<dyn Wait + Send + Sync> {
+        match self {
+            WaitStrategy::Fixed { duration } => Box::new(WaitFixed::new(duration)),
+            WaitStrategy::Random { min, max } => Box::new(WaitRandom::new(min, max)),
+            WaitStrategy::Exponential {
+                multiplier,
+                min,
+                max,
+                exp_base,
+            } => Box::new(WaitExponential::new(multiplier, min, max, exp_base)),
+        }
     }
 }
 
@@ -75,55 +225,134 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_fixed_wait_seconds() {
+    fn test_fixed_wait_duration() {
         let ctx = RetryingContext::default();
 
-        let wait = WaitFixed { seconds: 10f32 };
+        let wait = WaitFixed {
+            duration: Duration::from_secs(10),
+        };
 
+        assert_eq!(wait.wait_duration(&ctx), Duration::from_secs(10));
         assert_eq!(wait.wait_seconds(&ctx), 10f32)
     }
 
     #[test]
-    fn test_random_wait_seconds() {
+    fn test_random_wait_duration() {
         let ctx = RetryingContext::default();
 
         let wait = WaitRandom {
-            min: 1f32,
-            max: 10.5f32,
+            min: Duration::from_secs(1),
+            max: Duration::from_millis(10500),
         };
 
-        assert!((1f32..=10.5f32).contains(&wait.wait_seconds(&ctx)))
+        assert!((Duration::from_secs(1)..=Duration::from_millis(10500))
+            .contains(&wait.wait_duration(&ctx)))
     }
 
     #[test]
-    fn test_exponential_wait_seconds() {
+    fn test_exponential_wait_duration() {
         let mut ctx = RetryingContext::default();
 
         let wait = WaitExponential {
-            multiplier: 0.5,
-            min: 1f32,
-            max: 10.5f32,
+            multiplier: Duration::from_millis(500),
+            min: Duration::from_secs(1),
+            max: Duration::from_millis(10500),
             exp_base: 2,
         };
 
-        assert_eq!(wait.wait_seconds(&ctx), 1.5f32);
+        assert_eq!(wait.wait_duration(&ctx), Duration::from_millis(1500));
 
         ctx.add_attempt();
-        assert_eq!(wait.wait_seconds(&ctx), 2.0f32);
+        assert_eq!(wait.wait_duration(&ctx), Duration::from_secs(2));
 
         ctx.add_attempt();
-        assert_eq!(wait.wait_seconds(&ctx), 3.0f32);
+        assert_eq!(wait.wait_duration(&ctx), Duration::from_secs(3));
 
         ctx.add_attempt();
-        assert_eq!(wait.wait_seconds(&ctx), 5.0f32);
+        assert_eq!(wait.wait_duration(&ctx), Duration::from_secs(5));
 
         ctx.add_attempt();
-        assert_eq!(wait.wait_seconds(&ctx), 9.0f32);
+        assert_eq!(wait.wait_duration(&ctx), Duration::from_secs(9));
 
         ctx.add_attempt();
-        assert_eq!(wait.wait_seconds(&ctx), 10.5f32);
+        assert_eq!(wait.wait_duration(&ctx), Duration::from_millis(10500));
 
         ctx.add_attempt();
-        assert_eq!(wait.wait_seconds(&ctx), 10.5f32);
+        assert_eq!(wait.wait_duration(&ctx), Duration::from_millis(10500));
+
+        for _ in 0..100 {
+            ctx.add_attempt();
+        }
+        assert_eq!(wait.wait_duration(&ctx), Duration::from_millis(10500));
+    }
+
+    #[test]
+    fn test_wait_strategy_from_name() {
+        assert_eq!(
+            WaitStrategy::from_name("Fixed"),
+            Some(WaitStrategy::Fixed {
+                duration: Duration::ZERO
+            })
+        );
+        assert_eq!(
+            WaitStrategy::from_name(" random "),
+            Some(WaitStrategy::Random {
+                min: Duration::ZERO,
+                max: Duration::from_secs(3600)
+            })
+        );
+        assert_eq!(
+            WaitStrategy::from_name("EXPONENTIAL"),
+            Some(WaitStrategy::Exponential {
+                multiplier: Duration::from_secs(1),
+                min: Duration::ZERO,
+                max: Duration::from_secs(3600),
+                exp_base: 2
+            })
+        );
+        assert_eq!(WaitStrategy::from_name("linear"), None);
+    }
+
+    #[test]
+    fn test_wait_strategy_build() {
+        let ctx = RetryingContext::default();
+
+        let wait = WaitStrategy::Exponential {
+            multiplier: Duration::from_millis(500),
+            min: Duration::from_secs(1),
+            max: Duration::from_millis(10500),
+            exp_base: 2,
+        }
+        .build();
+
+        assert_eq!(wait.wait_duration(&ctx), Duration::from_millis(1500));
+    }
+
+    #[test]
+    fn test_wait_strategy_validate() {
+        assert!(WaitStrategy::Random {
+            min: Duration::from_secs(1),
+            max: Duration::from_secs(2)
+        }
+        .validate()
+        .is_ok());
+        assert_eq!(
+            WaitStrategy::Random {
+                min: Duration::from_secs(3),
+                max: Duration::from_secs(2)
+            }
+            .validate(),
+            Err(RetryingError::new(
+                "random wait `min` (3s) should not be greater than `max` (2s)"
+            ))
+        );
+        assert!(WaitStrategy::Exponential {
+            multiplier: Duration::from_secs(1),
+            min: Duration::ZERO,
+            max: Duration::from_secs(1),
+            exp_base: 0
+        }
+        .validate()
+        .is_err());
     }
 }
@@ -0,0 +1,90 @@
+use retrying::abort::RetryAbort;
+use retrying::retry;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Error {
+    Timeout(u32),
+    NotFound,
+}
+
+mod shutdown {
+    pub static HANDLE: retrying::abort::RetryAbort = retrying::abort::RetryAbort::new();
+}
+
+static ABORTED: RetryAbort = RetryAbort::new();
+
+#[retry(stop=attempts(3),wait=fixed(60),abort=shutdown::HANDLE)]
+fn abort_wait(calls: &mut u32) -> Result<u32, Error> {
+    *calls += 1;
+    Err(Error::Timeout(*calls))
+}
+
+#[retry(stop=attempts(3),wait=fixed("1ms"),retry=if_errors(Error::Timeout),abort=ABORTED,clone_args=true)]
+fn abort_before_wait(calls: &mut u32, error: Error) -> Result<u32, Error> {
+    *calls += 1;
+    Err(error)
+}
+
+#[retry(stop=attempts(3),abort=handle)]
+fn abort_without_wait(handle: &RetryAbort, calls: &mut u32) -> Option<u32> {
+    *calls += 1;
+    if *calls > 1 {
+        handle.abort();
+    }
+    None
+}
+
+#[retry(stop=attempts(3),wait=fixed("1ms"),abort=handle)]
+fn not_aborted(handle: Arc<RetryAbort>, calls: &mut u32) -> Result<u32, Error> {
+    *calls += 1;
+    if *calls < 3 {
+        Err(Error::Timeout(*calls))
+    } else {
+        Ok(*calls)
+    }
+}
+
+#[test]
+fn test_abort_during_wait() {
+    let started = Instant::now();
+    let worker = std::thread::spawn(|| {
+        let mut calls = 0;
+        abort_wait(&mut calls)
+    });
+    std::thread::sleep(Duration::from_millis(20));
+    shutdown::HANDLE.abort();
+
+    assert_eq!(worker.join().unwrap(), Err(Error::Timeout(1)));
+    assert!(started.elapsed() < Duration::from_secs(10));
+}
+
+#[test]
+fn test_aborted_before_wait() {
+    ABORTED.abort();
+    let mut calls = 0;
+    assert_eq!(
+        abort_before_wait(&mut calls, Error::Timeout(0)),
+        Err(Error::Timeout(0))
+    );
+    assert_eq!(calls, 1);
+    assert_eq!(
+        abort_before_wait(&mut calls, Error::NotFound),
+        Err(Error::NotFound)
+    );
+}
+
+#[test]
+fn test_abort_without_wait() {
+    let handle = RetryAbort::new();
+    let mut calls = 0;
+    assert_eq!(abort_without_wait(&handle, &mut calls), None);
+    assert_eq!(calls, 2);
+}
+
+#[test]
+fn test_not_aborted() {
+    let mut calls = 0;
+    assert_eq!(not_aborted(Arc::new(RetryAbort::new()), &mut calls), Ok(3));
+}
@@ -0,0 +1,109 @@
+#![cfg(feature = "tokio")]
+
+use retrying::cancel::Cancelled;
+use retrying::retry;
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+#[derive(Debug, PartialEq)]
+enum Error {
+    Timeout,
+    Cancelled,
+}
+
+impl From<Cancelled> for Error {
+    fn from(_: Cancelled) -> Self {
+        Error::Cancelled
+    }
+}
+
+#[retry(stop=attempts(3),wait=fixed(60),cancel=shutdown.notified())]
+async fn cancel_wait(shutdown: &Notify, calls: &mut u32) -> Result<u32, Error> {
+    *calls += 1;
+    Err(Error::Timeout)
+}
+
+#[retry(stop=attempts(3),cancel=shutdown.notified())]
+async fn cancel_attempt(shutdown: &Notify, calls: &mut u32) -> Result<u32, Cancelled> {
+    *calls += 1;
+    tokio::time::sleep(Duration::from_secs(60)).await;
+    Ok(*calls)
+}
+
+#[retry(stop=attempts(3),wait=fixed("1ms"),cancel=shutdown.notified())]
+async fn cancel_long_attempt(shutdown: &Notify, calls: &mut u32) -> Result<u32, Error> {
+    *calls += 1;
+    if *calls == 1 {
+        return Err(Error::Timeout);
+    }
+    tokio::time::sleep(Duration::from_secs(60)).await;
+    Ok(*calls)
+}
+
+#[retry(stop=attempts(3),wait=fixed("1ms"),cancel=tokio::time::sleep(Duration::from_secs(60)))]
+async fn not_cancelled(calls: &mut u32) -> Result<u32, Error> {
+    *calls += 1;
+    if *calls < 3 {
+        Err(Error::Timeout)
+    } else {
+        Ok(*calls)
+    }
+}
+
+#[tokio::test]
+async fn test_cancel_during_wait() {
+    let shutdown = Notify::new();
+    let mut calls = 0;
+    let started = Instant::now();
+    let (result, _) = tokio::join!(cancel_wait(&shutdown, &mut calls), async {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        shutdown.notify_one();
+    });
+    assert_eq!(result, Err(Error::Cancelled));
+    assert_eq!(calls, 1);
+    assert!(started.elapsed() < Duration::from_secs(10));
+}
+
+#[tokio::test]
+async fn test_cancel_during_attempt() {
+    let shutdown = Notify::new();
+    let mut calls = 0;
+    let (result, _) = tokio::join!(cancel_attempt(&shutdown, &mut calls), async {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        shutdown.notify_one();
+    });
+    assert_eq!(result, Err(Cancelled));
+    assert_eq!(calls, 1);
+}
+
+#[tokio::test]
+async fn test_cancel_long_running_attempt_after_retry() {
+    let shutdown = Notify::new();
+    let mut calls = 0;
+    let started = Instant::now();
+    let (result, _) = tokio::join!(cancel_long_attempt(&shutdown, &mut calls), async {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        shutdown.notify_one();
+    });
+    assert_eq!(result, Err(Error::Cancelled));
+    assert_eq!(calls, 2);
+    assert!(started.elapsed() < Duration::from_secs(10));
+}
+
+#[tokio::test]
+async fn test_cancelled_before_call() {
+    let shutdown = Notify::new();
+    shutdown.notify_one();
+    let mut calls = 0;
+    assert_eq!(
+        cancel_wait(&shutdown, &mut calls).await,
+        Err(Error::Cancelled)
+    );
+    assert_eq!(calls, 0);
+}
+
+#[tokio::test]
+async fn test_not_cancelled() {
+    let mut calls = 0;
+    assert_eq!(not_cancelled(&mut calls).await, Ok(3));
+}
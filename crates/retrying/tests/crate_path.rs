@@ -0,0 +1,23 @@
+pub extern crate retrying as retry_lib;
+
+mod facade {
+    pub use retry_lib as retrying;
+}
+
+#[retry_lib::retry(stop=attempts(2),wait=fixed(0),crate="::retry_lib")]
+fn renamed_dependency(value: &str) -> Result<u32, std::num::ParseIntError> {
+    value.parse::<u32>()
+}
+
+#[retry_lib::retry(stop=attempts(2),envs_prefix="crate_path",crate="facade::retrying")]
+fn reexported_crate(value: &str) -> Option<u32> {
+    value.parse::<u32>().ok()
+}
+
+#[test]
+fn test_crate_path() {
+    assert_eq!(renamed_dependency("1"), Ok(1));
+    assert!(renamed_dependency("x").is_err());
+    assert_eq!(reexported_crate("2"), Some(2));
+    assert_eq!(reexported_crate("x"), None);
+}
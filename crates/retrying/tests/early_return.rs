@@ -0,0 +1,91 @@
+use retrying::retry;
+use std::num::ParseIntError;
+
+#[derive(Debug, PartialEq)]
+struct Error {}
+
+impl From<ParseIntError> for Error {
+    fn from(_: ParseIntError) -> Self {
+        Error {}
+    }
+}
+
+#[retry(stop=attempts(3))]
+fn question_mark(calls: &mut u32, value: &str) -> Result<u32, Error> {
+    *calls += 1;
+    let value = value.parse::<u32>()?;
+    Ok(value)
+}
+
+#[retry(stop=attempts(3))]
+fn early_return(calls: &mut u32) -> Option<u32> {
+    *calls += 1;
+    if *calls < 2 {
+        return None;
+    }
+    Some(*calls)
+}
+
+#[retry(stop=attempts(3),wrap_body=false)]
+fn early_return_without_retry(calls: &mut u32) -> Result<u32, Error> {
+    *calls += 1;
+    if *calls < 2 {
+        return Err(Error {});
+    }
+    Ok(*calls)
+}
+
+#[retry(stop=attempts(3))]
+async fn question_mark_async(calls: &mut u32, value: &str) -> Result<u32, Error> {
+    *calls += 1;
+    let value = value.parse::<u32>()?;
+    Ok(value)
+}
+
+#[retry(stop=attempts(3))]
+async fn early_return_async(calls: &mut u32) -> Result<u32, Error> {
+    *calls += 1;
+    if *calls < 2 {
+        return Err(Error {});
+    }
+    Ok(*calls)
+}
+
+#[test]
+fn test_question_mark_is_retried() {
+    let mut calls = 0;
+    assert_eq!(question_mark(&mut calls, "x"), Err(Error {}));
+    assert_eq!(calls, 3);
+
+    let mut calls = 0;
+    assert_eq!(question_mark(&mut calls, "5"), Ok(5));
+    assert_eq!(calls, 1);
+}
+
+#[test]
+fn test_return_is_retried() {
+    let mut calls = 0;
+    assert_eq!(early_return(&mut calls), Some(2));
+    assert_eq!(calls, 2);
+}
+
+#[test]
+fn test_return_without_wrapped_body() {
+    let mut calls = 0;
+    assert_eq!(early_return_without_retry(&mut calls), Err(Error {}));
+    assert_eq!(calls, 1);
+}
+
+#[tokio::test]
+async fn test_question_mark_is_retried_async() {
+    let mut calls = 0;
+    assert_eq!(question_mark_async(&mut calls, "x").await, Err(Error {}));
+    assert_eq!(calls, 3);
+}
+
+#[tokio::test]
+async fn test_return_is_retried_async() {
+    let mut calls = 0;
+    assert_eq!(early_return_async(&mut calls).await, Ok(2));
+    assert_eq!(calls, 2);
+}
@@ -0,0 +1,43 @@
+use retrying::retry;
+
+/// type with the same name as `retrying::stop::Stop` trait.
+#[derive(Debug, PartialEq)]
+struct Stop(u32);
+
+/// trait with the same method name as `retrying::wait::Wait` trait.
+trait Wait {
+    fn wait_duration(&self) -> u32;
+}
+
+impl Wait for Stop {
+    fn wait_duration(&self) -> u32 {
+        self.0
+    }
+}
+
+#[retry(stop=attempts(3),wait=fixed(0),retry=if_errors(ParseError))]
+fn parse(retrying_context: &str, err: u32) -> Result<Stop, ParseError> {
+    let retrying_stop = Stop(err);
+    let retrying_result = retrying_stop.wait_duration();
+    retrying_context
+        .parse::<u32>()
+        .map(|value| Stop(value + retrying_result))
+        .map_err(|_| ParseError {})
+}
+
+#[retry(stop=attempts(3),envs_prefix="hygiene")]
+fn parse_with_envs(retrying_policy: &str) -> Option<u32> {
+    let retrying_wait = retrying_policy.parse::<u32>();
+    retrying_wait.ok()
+}
+
+#[derive(Debug, PartialEq)]
+struct ParseError {}
+
+#[test]
+fn test_generated_variables_are_hidden() {
+    assert_eq!(parse("1", 2), Ok(Stop(3)));
+    assert_eq!(parse("x", 2), Err(ParseError {}));
+    assert_eq!(parse_with_envs("5"), Some(5));
+    assert_eq!(parse_with_envs("x"), None);
+}
@@ -0,0 +1,102 @@
+use retrying::retry;
+use std::num::ParseIntError;
+
+struct Counter {
+    calls: u32,
+    succeed_on: u32,
+}
+
+impl Counter {
+    fn new(succeed_on: u32) -> Counter {
+        Counter {
+            calls: 0,
+            succeed_on,
+        }
+    }
+
+    #[retry(stop=attempts(5))]
+    fn increment(&mut self) -> Result<u32, u32> {
+        self.calls += 1;
+        if self.calls == self.succeed_on {
+            Ok(self.calls)
+        } else {
+            Err(self.calls)
+        }
+    }
+
+    #[retry(stop=attempts(2))]
+    fn parse(&self, value: &str) -> Result<u32, ParseIntError> {
+        value.parse::<u32>().map(|v| v + self.calls)
+    }
+}
+
+trait Parser {
+    fn parse_value(&self, value: String) -> Result<u32, ParseIntError>;
+}
+
+struct ConsumingParser;
+
+impl Parser for ConsumingParser {
+    #[retry(stop=attempts(2),clone_args=true)]
+    fn parse_value(&self, value: String) -> Result<u32, ParseIntError> {
+        let owned: String = value;
+        owned.parse::<u32>()
+    }
+}
+
+#[retry(stop=attempts(3),clone_args=true)]
+fn consume<T: Clone + Into<String>>(value: T, mut suffix: String) -> Result<String, String> {
+    suffix.push('!');
+    let value: String = value.into();
+    if suffix.len() > 2 {
+        Ok(value + &suffix)
+    } else {
+        Err(value + &suffix)
+    }
+}
+
+#[retry(stop=attempts(3))]
+fn generic<T>(values: &[T], index: usize) -> Option<&T>
+where
+    T: PartialEq,
+{
+    values.get(index)
+}
+
+#[test]
+fn test_retry_mut_self_method() {
+    let mut counter = Counter::new(3);
+
+    assert_eq!(counter.increment(), Ok(3));
+    assert_eq!(counter.calls, 3);
+
+    let mut counter = Counter::new(10);
+    assert_eq!(counter.increment(), Err(5));
+}
+
+#[test]
+fn test_retry_self_method() {
+    let counter = Counter::new(1);
+
+    assert_eq!(counter.parse("1"), Ok(1));
+    assert!(counter.parse("a").is_err());
+}
+
+#[test]
+fn test_retry_trait_impl_method_with_clone_args() {
+    assert_eq!(ConsumingParser.parse_value(String::from("5")), Ok(5));
+    assert!(ConsumingParser.parse_value(String::from("a")).is_err());
+}
+
+#[test]
+fn test_retry_generic_function_with_clone_args() {
+    // every attempt gets its own copy of arguments, so changes of previous attempts are not visible
+    assert_eq!(consume("a", String::from("b")), Err(String::from("ab!")));
+    assert_eq!(consume("a", String::from("bc")), Ok(String::from("abc!")));
+}
+
+#[test]
+fn test_retry_generic_function() {
+    assert_eq!(generic(&[1, 2], 1), Some(&2));
+    assert_eq!(generic(&[1, 2], 2), None);
+}
@@ -0,0 +1,96 @@
+#![cfg(feature = "metrics")]
+
+use retrying::cancel::Cancelled;
+use retrying::metrics::FunctionMetricsSnapshot;
+use retrying::retry;
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Error {
+    Timeout,
+    NotFound,
+}
+
+#[retry(stop=attempts(3),wait=fixed("1ms"),retry=if_errors(Error::Timeout),metrics_label="db",clone_args=true)]
+fn metrics_call(calls: &mut u32, errors: u32, error: Error) -> Result<u32, Error> {
+    *calls += 1;
+    if *calls <= errors {
+        Err(error)
+    } else {
+        Ok(*calls)
+    }
+}
+
+#[retry(stop=attempts(2))]
+async fn metrics_call_async(value: Option<u32>) -> Option<u32> {
+    value
+}
+
+#[retry(stop=attempts(3),wait=fixed(60),cancel=tokio::time::sleep(Duration::from_millis(10)))]
+async fn metrics_cancel_wait() -> Result<u32, Cancelled> {
+    Err(Cancelled)
+}
+
+#[retry(stop=attempts(3),cancel=std::future::ready(()))]
+async fn metrics_cancel_attempt() -> Result<u32, Cancelled> {
+    std::future::pending().await
+}
+
+fn function_metrics(function: &str) -> FunctionMetricsSnapshot {
+    retrying::metrics::snapshot()
+        .into_iter()
+        .find(|metrics| metrics.function == function)
+        .unwrap()
+}
+
+#[test]
+fn test_metrics() {
+    assert_eq!(metrics_call(&mut 0, 0, Error::Timeout), Ok(1));
+    assert_eq!(metrics_call(&mut 0, 1, Error::Timeout), Ok(2));
+    assert_eq!(metrics_call(&mut 0, 5, Error::Timeout), Err(Error::Timeout));
+    assert_eq!(
+        metrics_call(&mut 0, 5, Error::NotFound),
+        Err(Error::NotFound)
+    );
+
+    let metrics = function_metrics("metrics_call");
+    assert_eq!(metrics.label, Some("db"));
+    assert_eq!(metrics.calls, 4);
+    assert_eq!(metrics.attempts, 7);
+    assert_eq!(metrics.successes, 2);
+    assert_eq!(metrics.successes_after_retry, 1);
+    assert_eq!(metrics.exhausted, 1);
+    assert_eq!(metrics.non_retryable, 1);
+    assert_eq!(metrics.latency.count, 4);
+    assert_eq!(metrics.sleep.count, 4);
+    assert!(metrics.sleep.sum >= retrying::Duration::from_millis(3));
+}
+
+#[tokio::test]
+async fn test_metrics_async() {
+    assert_eq!(metrics_call_async(None).await, None);
+    assert_eq!(metrics_call_async(Some(1)).await, Some(1));
+
+    let metrics = function_metrics("metrics_call_async");
+    assert_eq!(metrics.label, None);
+    assert_eq!(metrics.calls, 2);
+    assert_eq!(metrics.attempts, 3);
+    assert_eq!(metrics.exhausted, 1);
+}
+
+#[tokio::test]
+async fn test_metrics_cancelled() {
+    assert_eq!(metrics_cancel_wait().await, Err(Cancelled));
+    assert_eq!(metrics_cancel_attempt().await, Err(Cancelled));
+
+    let metrics = function_metrics("metrics_cancel_wait");
+    assert_eq!(metrics.calls, 1);
+    assert_eq!(metrics.cancelled, 1);
+    assert_eq!(metrics.exhausted, 0);
+    assert_eq!(metrics.latency.count, 1);
+
+    let metrics = function_metrics("metrics_cancel_attempt");
+    assert_eq!(metrics.calls, 1);
+    assert_eq!(metrics.attempts, 1);
+    assert_eq!(metrics.cancelled, 1);
+}
@@ -0,0 +1,166 @@
+#![cfg(feature = "opentelemetry")]
+
+use opentelemetry::trace::{Tracer, TracerProvider};
+use opentelemetry::{KeyValue, Value};
+use opentelemetry_sdk::trace::{
+    InMemorySpanExporter, InMemorySpanExporterBuilder, SdkTracerProvider, SimpleSpanProcessor,
+    SpanData,
+};
+use retrying::retry;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Error {
+    Timeout,
+    NotFound,
+}
+
+#[retry(stop=attempts(3),wait=fixed("1ms"),retry=if_errors(Error::Timeout),metrics_label="db",clone_args=true)]
+fn otel_call(calls: &mut u32, errors: u32, error: Error) -> Result<u32, Error> {
+    *calls += 1;
+    if *calls <= errors {
+        Err(error)
+    } else {
+        Ok(*calls)
+    }
+}
+
+#[retry(stop=attempts(2))]
+async fn otel_call_async(value: Option<u32>) -> Option<u32> {
+    value
+}
+
+#[retry(stop=attempts(2),cancel=std::future::ready(()))]
+async fn otel_call_cancelled() -> Result<u32, retrying::cancel::Cancelled> {
+    std::future::pending().await
+}
+
+fn traced<T>(name: &'static str, f: impl FnOnce() -> T) -> (T, SpanData) {
+    let exporter: InMemorySpanExporter = InMemorySpanExporterBuilder::new().build();
+    let provider = SdkTracerProvider::builder()
+        .with_span_processor(SimpleSpanProcessor::new(exporter.clone()))
+        .build();
+    let result = provider.tracer("retrying").in_span(name, |_cx| f());
+    let mut spans = exporter.get_finished_spans().unwrap();
+    assert_eq!(spans.len(), 1);
+    (result, spans.remove(0))
+}
+
+fn attribute<'a>(attributes: &'a [KeyValue], key: &str) -> Option<&'a Value> {
+    attributes
+        .iter()
+        .find(|attribute| attribute.key.as_str() == key)
+        .map(|attribute| &attribute.value)
+}
+
+#[test]
+fn test_opentelemetry_success_after_retry() {
+    let (result, span) = traced("success", || otel_call(&mut 0, 2, Error::Timeout));
+    assert_eq!(result, Ok(3));
+
+    let attributes = &span.attributes;
+    assert_eq!(
+        attribute(attributes, "retry.function"),
+        Some(&Value::from("otel_call"))
+    );
+    assert_eq!(
+        attribute(attributes, "retry.label"),
+        Some(&Value::from("db"))
+    );
+    assert_eq!(attribute(attributes, "retry.attempt"), Some(&Value::I64(3)));
+    assert_eq!(attribute(attributes, "retry.wait_ms"), Some(&Value::I64(2)));
+    assert_eq!(
+        attribute(attributes, "retry.outcome"),
+        Some(&Value::from("success"))
+    );
+
+    let events: Vec<_> = span.events.iter().collect();
+    assert_eq!(events.len(), 2);
+    for (event, attempt) in events.into_iter().zip([2, 3]) {
+        assert_eq!(event.name, "retry");
+        assert_eq!(
+            attribute(&event.attributes, "retry.attempt"),
+            Some(&Value::I64(attempt))
+        );
+        assert_eq!(
+            attribute(&event.attributes, "retry.wait_ms"),
+            Some(&Value::I64(1))
+        );
+        assert_eq!(
+            attribute(&event.attributes, "retry.outcome"),
+            Some(&Value::from("retry"))
+        );
+    }
+}
+
+#[test]
+fn test_opentelemetry_exhausted_and_non_retryable() {
+    let (result, span) = traced("exhausted", || otel_call(&mut 0, 5, Error::Timeout));
+    assert_eq!(result, Err(Error::Timeout));
+    assert_eq!(
+        attribute(&span.attributes, "retry.outcome"),
+        Some(&Value::from("exhausted"))
+    );
+    assert_eq!(
+        attribute(&span.attributes, "retry.attempt"),
+        Some(&Value::I64(3))
+    );
+    assert_eq!(span.events.len(), 2);
+
+    let (result, span) = traced("non_retryable", || otel_call(&mut 0, 5, Error::NotFound));
+    assert_eq!(result, Err(Error::NotFound));
+    assert_eq!(
+        attribute(&span.attributes, "retry.outcome"),
+        Some(&Value::from("non_retryable"))
+    );
+    assert_eq!(
+        attribute(&span.attributes, "retry.attempt"),
+        Some(&Value::I64(1))
+    );
+    assert_eq!(span.events.len(), 0);
+}
+
+#[test]
+fn test_opentelemetry_async_without_wait() {
+    let (result, span) = traced("async", || {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(otel_call_async(None))
+    });
+    assert_eq!(result, None);
+    assert_eq!(attribute(&span.attributes, "retry.label"), None);
+    assert_eq!(
+        attribute(&span.attributes, "retry.outcome"),
+        Some(&Value::from("exhausted"))
+    );
+    assert_eq!(
+        attribute(&span.attributes, "retry.wait_ms"),
+        Some(&Value::I64(0))
+    );
+    assert_eq!(span.events.len(), 1);
+    assert_eq!(
+        attribute(&span.events[0].attributes, "retry.wait_ms"),
+        Some(&Value::I64(0))
+    );
+}
+
+#[test]
+fn test_opentelemetry_cancelled() {
+    let (result, span) = traced("cancelled", || {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(otel_call_cancelled())
+    });
+    assert_eq!(result, Err(retrying::cancel::Cancelled));
+    assert_eq!(
+        attribute(&span.attributes, "retry.outcome"),
+        Some(&Value::from("cancelled"))
+    );
+    assert_eq!(
+        attribute(&span.attributes, "retry.attempt"),
+        Some(&Value::I64(1))
+    );
+}
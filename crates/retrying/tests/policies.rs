@@ -0,0 +1,143 @@
+use retrying::retry;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    Timeout,
+    NotFound,
+}
+
+mod db {
+    retrying::policy!(
+        pub(crate) DB_POLICY,
+        stop=attempts(3),
+        wait=fixed("1ms"),
+        retry=if_errors(crate::Error::Timeout)
+    );
+
+    retrying::policy!(pub(crate) EMPTY_POLICY);
+}
+
+retrying::policy!(LOCAL_POLICY, stop = attempts(2));
+
+pub mod exported {
+    retrying::policy!(pub EXPORTED_POLICY, stop = attempts(2), wait = fixed("1ms"));
+}
+
+fn fail(calls: &AtomicU32, error: Error) -> Result<u32, Error> {
+    calls.fetch_add(1, Ordering::SeqCst);
+    Err(error)
+}
+
+#[retry(policy=db::DB_POLICY)]
+fn timeout(calls: &AtomicU32) -> Result<u32, Error> {
+    fail(calls, Error::Timeout)
+}
+
+#[retry(policy=db::DB_POLICY)]
+fn not_found(calls: &AtomicU32) -> Result<u32, Error> {
+    fail(calls, Error::NotFound)
+}
+
+#[retry(policy=db::DB_POLICY, stop=attempts(2), retry=if_not_errors(Error::Timeout), clone_args=true)]
+fn overridden(calls: &AtomicU32, error: Error) -> Result<u32, Error> {
+    fail(calls, error)
+}
+
+#[retry(stop=attempts(4), policy=db::EMPTY_POLICY)]
+fn empty_policy(calls: &AtomicU32) -> Option<u32> {
+    calls.fetch_add(1, Ordering::SeqCst);
+    None
+}
+
+#[retry(policy=db::DB_POLICY, policy="policies_test_registry")]
+fn registry_policy(calls: &AtomicU32) -> Result<u32, Error> {
+    fail(calls, Error::Timeout)
+}
+
+#[retry(policy=exported::EXPORTED_POLICY)]
+fn exported_policy(calls: &AtomicU32) -> Result<u32, Error> {
+    fail(calls, Error::NotFound)
+}
+
+#[retry(policy=LOCAL_POLICY)]
+async fn local_policy(calls: &AtomicU32) -> Option<u32> {
+    calls.fetch_add(1, Ordering::SeqCst);
+    None
+}
+
+#[test]
+fn test_policy() {
+    let calls = AtomicU32::new(0);
+    assert_eq!(timeout(&calls), Err(Error::Timeout));
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+    let calls = AtomicU32::new(0);
+    assert_eq!(not_found(&calls), Err(Error::NotFound));
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_policy_overrides() {
+    let calls = AtomicU32::new(0);
+    assert_eq!(overridden(&calls, Error::NotFound), Err(Error::NotFound));
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+    let calls = AtomicU32::new(0);
+    assert_eq!(overridden(&calls, Error::Timeout), Err(Error::Timeout));
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    let calls = AtomicU32::new(0);
+    assert_eq!(empty_policy(&calls), None);
+    assert_eq!(calls.load(Ordering::SeqCst), 4);
+}
+
+#[test]
+fn test_policy_definition_with_registry() {
+    let calls = AtomicU32::new(0);
+    assert_eq!(registry_policy(&calls), Err(Error::Timeout));
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+    retrying::PolicyRegistry::global().insert(
+        "policies_test_registry",
+        retrying::Policy::new(
+            Box::new(retrying::stop::StopAttempts::new(1)),
+            Box::new(retrying::wait::WaitFixed::new(retrying::Duration::ZERO)),
+        ),
+    );
+    let calls = AtomicU32::new(0);
+    assert_eq!(registry_policy(&calls), Err(Error::Timeout));
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_exported_policy() {
+    let calls = AtomicU32::new(0);
+    assert_eq!(exported_policy(&calls), Err(Error::NotFound));
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_local_policy() {
+    let calls = AtomicU32::new(0);
+    assert_eq!(local_policy(&calls).await, None);
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_policy_definition() {
+    assert_eq!(db::DB_POLICY.stop.attempts, Some(3));
+    assert_eq!(
+        db::DB_POLICY.wait,
+        Some(retrying::wait::WaitStrategy::Fixed {
+            duration: retrying::Duration::from_millis(1)
+        })
+    );
+
+    let calls = AtomicU32::new(0);
+    let result = db::DB_POLICY
+        .build()
+        .retry(|| fail(&calls, Error::NotFound));
+    assert_eq!(result, Err(Error::NotFound));
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
@@ -0,0 +1,99 @@
+use retrying::retry;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+#[test]
+fn test_retry_option() {
+    static CALLS: AtomicU32 = AtomicU32::new(0);
+
+    #[retry(stop=attempts(3))]
+    fn parse_on_third_call() -> Option<u32> {
+        let calls = CALLS.fetch_add(1, Ordering::SeqCst) + 1;
+        (calls == 3).then_some(calls)
+    }
+
+    assert_eq!(parse_on_third_call(), Some(3));
+    assert_eq!(CALLS.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn test_retry_option_stops_with_none() {
+    static CALLS: AtomicU32 = AtomicU32::new(0);
+
+    #[retry(stop=attempts(2))]
+    fn always_none() -> Option<u32> {
+        CALLS.fetch_add(1, Ordering::SeqCst);
+        None
+    }
+
+    assert_eq!(always_none(), None);
+    assert_eq!(CALLS.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_retry_result_alias() {
+    static CALLS: AtomicU32 = AtomicU32::new(0);
+
+    #[retry(stop=attempts(2))]
+    fn always_error() -> std::io::Result<u32> {
+        CALLS.fetch_add(1, Ordering::SeqCst);
+        Err(std::io::Error::new(std::io::ErrorKind::Other, "test"))
+    }
+
+    assert!(always_error().is_err());
+    assert_eq!(CALLS.load(Ordering::SeqCst), 2);
+}
+
+#[derive(Debug, PartialEq)]
+enum Status {
+    Ok,
+    Unavailable,
+    NotFound,
+}
+
+#[derive(Debug, PartialEq)]
+struct Response {
+    status: Status,
+}
+
+impl retrying::Retryable for Response {
+    type Ok = ();
+    type Error = Status;
+
+    fn outcome(&self) -> retrying::Outcome<'_, Status> {
+        match self.status {
+            Status::Ok => retrying::Outcome::Success,
+            Status::Unavailable => retrying::Outcome::Retry(&self.status),
+            Status::NotFound => retrying::Outcome::Abort(&self.status),
+        }
+    }
+
+    fn into_result(self) -> Result<(), Status> {
+        match self.status {
+            Status::Ok => Ok(()),
+            status => Err(status),
+        }
+    }
+}
+
+#[test]
+fn test_retry_custom_retryable() {
+    static CALLS: AtomicU32 = AtomicU32::new(0);
+
+    #[retry(stop=attempts(5))]
+    fn unavailable_then_not_found() -> Response {
+        let status = match CALLS.fetch_add(1, Ordering::SeqCst) {
+            0 | 1 => Status::Unavailable,
+            2 => Status::NotFound,
+            _ => Status::Ok,
+        };
+        Response { status }
+    }
+
+    assert_eq!(
+        unavailable_then_not_found(),
+        Response {
+            status: Status::NotFound
+        }
+    );
+    assert_eq!(CALLS.load(Ordering::SeqCst), 3);
+}
@@ -0,0 +1,68 @@
+use retrying::retry;
+use std::fmt::Display;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+#[retry(stop=attempts(2))]
+fn first<const N: usize>(values: [u32; N]) -> Option<u32> {
+    values.first().copied()
+}
+
+#[retry(stop=attempts(2))]
+fn longest<'a, 'b: 'a>(left: &'a str, right: &'b str) -> Result<&'a str, ()> {
+    if left.len() >= right.len() {
+        Ok(left)
+    } else {
+        Ok(right)
+    }
+}
+
+#[retry(stop=attempts(2))]
+fn parse<T>(value: &str) -> Result<T, T::Err>
+where
+    T: FromStr,
+{
+    value.parse::<T>()
+}
+
+#[retry(stop=attempts(2))]
+fn parse_impl(value: impl AsRef<str>) -> Result<u32, ParseIntError> {
+    value.as_ref().parse::<u32>()
+}
+
+#[retry(stop=attempts(2))]
+fn display(value: u32) -> Option<impl Display> {
+    Some(value)
+}
+
+#[retry(stop=attempts(2))]
+fn retryable(value: &str) -> impl retrying::Retryable<Ok = u32, Error = ParseIntError> {
+    value.parse::<u32>()
+}
+
+#[retry(stop=attempts(2))]
+extern "C" fn positive(value: &u32) -> Option<&u32> {
+    Some(value).filter(|value| **value > 0)
+}
+
+#[retry(stop=attempts(2))]
+unsafe fn read(value: *const u32) -> Option<u32> {
+    value.as_ref().copied()
+}
+
+#[test]
+fn test_signatures() {
+    use retrying::Retryable;
+
+    assert_eq!(first([1, 2, 3]), Some(1));
+    assert_eq!(first([]), None);
+    assert_eq!(longest("ab", "abc"), Ok("abc"));
+    assert_eq!(parse::<u8>("7"), Ok(7));
+    assert!(parse::<u8>("300").is_err());
+    assert_eq!(parse_impl(String::from("5")), Ok(5));
+    assert_eq!(display(3).map(|v| v.to_string()), Some(String::from("3")));
+    assert_eq!(retryable("4").into_result(), Ok(4));
+    assert_eq!(positive(&1), Some(&1));
+    assert_eq!(positive(&0), None);
+    assert_eq!(unsafe { read(&8) }, Some(8));
+}
@@ -0,0 +1,64 @@
+use retrying::retry;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Error {
+    Unavailable(u32),
+}
+
+mod flags {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    pub static SHUTDOWN: AtomicBool = AtomicBool::new(false);
+
+    pub fn is_shutdown(_ctx: &retrying::RetryingContext) -> bool {
+        SHUTDOWN.load(Ordering::SeqCst)
+    }
+}
+
+static CALLS: AtomicU32 = AtomicU32::new(0);
+static ENV_SHUTDOWN: AtomicBool = AtomicBool::new(false);
+
+fn env_shutdown(_ctx: &retrying::RetryingContext) -> bool {
+    ENV_SHUTDOWN.load(Ordering::SeqCst)
+}
+
+#[retry(stop=(attempts(10)|when(flags::is_shutdown)))]
+fn shutdown_after_calls(calls: &mut u32) -> Result<u32, Error> {
+    *calls += 1;
+    if *calls == 2 {
+        flags::SHUTDOWN.store(true, Ordering::SeqCst);
+    }
+    Err(Error::Unavailable(*calls))
+}
+
+#[retry(stop=when(three_calls))]
+fn only_predicate() -> Result<u32, Error> {
+    Err(Error::Unavailable(CALLS.fetch_add(1, Ordering::SeqCst) + 1))
+}
+
+fn three_calls(_ctx: &retrying::RetryingContext) -> bool {
+    CALLS.load(Ordering::SeqCst) >= 3
+}
+
+#[retry(stop=(attempts(5)|when(env_shutdown)),envs_prefix="stop_when_test")]
+fn predicate_with_envs(calls: &mut u32) -> Result<u32, Error> {
+    *calls += 1;
+    ENV_SHUTDOWN.store(true, Ordering::SeqCst);
+    Err(Error::Unavailable(*calls))
+}
+
+#[test]
+fn test_stop_when() {
+    let mut calls = 0;
+    assert_eq!(shutdown_after_calls(&mut calls), Err(Error::Unavailable(2)));
+    assert_eq!(calls, 2);
+
+    assert_eq!(only_predicate(), Err(Error::Unavailable(3)));
+}
+
+#[test]
+fn test_stop_when_with_envs() {
+    let mut calls = 0;
+    assert_eq!(predicate_with_envs(&mut calls), Err(Error::Unavailable(1)));
+}
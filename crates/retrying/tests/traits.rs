@@ -0,0 +1,81 @@
+use async_trait::async_trait;
+use retrying::retry;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+trait Service {
+    fn calls(&self) -> &AtomicU32;
+
+    #[retry(stop=attempts(3))]
+    fn call(&self) -> Result<u32, u32> {
+        let calls = self.calls().fetch_add(1, Ordering::SeqCst) + 1;
+        if calls == 2 {
+            Ok(calls)
+        } else {
+            Err(calls)
+        }
+    }
+
+    #[retry(stop=attempts(3))]
+    async fn call_async(&self) -> Option<u32> {
+        let calls = self.calls().fetch_add(1, Ordering::SeqCst) + 1;
+        (calls == 2).then_some(calls)
+    }
+}
+
+#[async_trait]
+trait AsyncService {
+    async fn call(&self, value: String) -> Result<u32, u32>;
+}
+
+#[derive(Default)]
+struct Client {
+    calls: AtomicU32,
+}
+
+impl Service for Client {
+    fn calls(&self) -> &AtomicU32 {
+        &self.calls
+    }
+}
+
+#[async_trait]
+impl AsyncService for Client {
+    #[retry(stop=attempts(3),clone_args=true)]
+    async fn call(&self, value: String) -> Result<u32, u32> {
+        let calls = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+        let value: String = value;
+        if value.len() as u32 == calls {
+            Ok(calls)
+        } else {
+            Err(calls)
+        }
+    }
+}
+
+#[test]
+fn test_retry_trait_default_method() {
+    let client = Client::default();
+
+    assert_eq!(Service::call(&client), Ok(2));
+    assert_eq!(client.calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_retry_trait_default_async_method() {
+    let client = Client::default();
+
+    assert_eq!(client.call_async().await, Some(2));
+    assert_eq!(client.calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_retry_async_trait_method() {
+    let client = Client::default();
+    assert_eq!(AsyncService::call(&client, String::from("ab")).await, Ok(2));
+
+    let client = Client::default();
+    assert_eq!(
+        AsyncService::call(&client, String::from("abcd")).await,
+        Err(3)
+    );
+}
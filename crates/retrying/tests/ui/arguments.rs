@@ -0,0 +1,22 @@
+use retrying::retry;
+
+struct Client;
+
+impl Client {
+    #[retry(stop=attempts(3))]
+    fn consume(self) -> Result<(), ()> {
+        Ok(())
+    }
+
+    #[retry(stop=attempts(3))]
+    fn consume_box(self: Box<Self>) -> Result<(), ()> {
+        Ok(())
+    }
+}
+
+#[retry(stop=attempts(3),clone_args=true)]
+fn destructured((a, b): (String, String)) -> Result<String, ()> {
+    Ok(a + &b)
+}
+
+fn main() {}
@@ -0,0 +1,23 @@
+use retrying::retry;
+
+#[retry(envs_prefix=TEST)]
+fn prefix_not_string() -> Result<(), ()> {
+    Ok(())
+}
+
+#[retry(envs_prefix="TEST", envs_strict="true")]
+fn strict_not_bool() -> Result<(), ()> {
+    Ok(())
+}
+
+#[retry(envs_strict=true)]
+fn strict_without_prefix() -> Result<(), ()> {
+    Ok(())
+}
+
+#[retry(policy="")]
+fn empty_policy() -> Result<(), ()> {
+    Ok(())
+}
+
+fn main() {}
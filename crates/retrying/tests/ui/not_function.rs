@@ -0,0 +1,6 @@
+use retrying::retry;
+
+#[retry(stop=attempts(3))]
+struct NotFunction;
+
+fn main() {}
@@ -0,0 +1,50 @@
+use retrying::retry;
+
+#[retry(stop=attempts(3) wait=fixed(1))]
+fn wrong_syntax() -> Result<(), ()> {
+    Ok(())
+}
+
+#[retry(stop)]
+fn not_name_value() -> Result<(), ()> {
+    Ok(())
+}
+
+#[retry(stpo=attempts(3))]
+fn misspelled_option() -> Result<(), ()> {
+    Ok(())
+}
+
+#[retry(config::stop=attempts(3))]
+fn option_path() -> Result<(), ()> {
+    Ok(())
+}
+
+#[retry(stop=attempts(1 + 2))]
+fn unsupported_value() -> Result<(), ()> {
+    Ok(())
+}
+
+#[retry(crate="retry lib")]
+fn wrong_crate_path() -> Result<(), ()> {
+    Ok(())
+}
+
+#[retry(stop=attempts(3),cancel=std::future::pending::<()>())]
+fn cancel_sync() -> Result<(), ()> {
+    Ok(())
+}
+
+#[retry(stop=attempts(3),wrap_body=false,cancel=std::future::pending::<()>())]
+async fn cancel_without_wrap_body() -> Result<(), ()> {
+    Ok(())
+}
+
+static SHUTDOWN: retrying::abort::RetryAbort = retrying::abort::RetryAbort::new();
+
+#[retry(stop=attempts(3),abort=SHUTDOWN)]
+async fn abort_async() -> Result<(), ()> {
+    Ok(())
+}
+
+fn main() {}
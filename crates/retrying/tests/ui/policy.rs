@@ -0,0 +1,14 @@
+retrying::policy!("DB_POLICY", stop=attempts(3));
+
+retrying::policy!(NESTED_POLICY, stop=attempts(3), policy="database");
+
+retrying::policy!(WRONG_POLICY, stop=attemps(3));
+
+retrying::policy!(LOCAL_POLICY, stop=attempts(3));
+
+#[retrying::retry(policy=LOCAL_POLICY, policy=NESTED_POLICY)]
+fn repeated_policy_path() -> Option<u32> {
+    None
+}
+
+fn main() {}
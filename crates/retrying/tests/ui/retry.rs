@@ -0,0 +1,18 @@
+use retrying::retry;
+
+#[retry(retry=if_error(std::num::ParseIntError))]
+fn misspelled_retry() -> Result<(), std::num::ParseIntError> {
+    Ok(())
+}
+
+#[retry(retry=(if_errors(std::num::ParseIntError)|if_not_errors(std::num::ParseFloatError)))]
+fn both_retry_conditions() -> Result<(), std::num::ParseIntError> {
+    Ok(())
+}
+
+#[retry(retry=if_errors("ParseIntError"))]
+fn error_as_string() -> Result<(), std::num::ParseIntError> {
+    Ok(())
+}
+
+fn main() {}
@@ -0,0 +1,24 @@
+use retrying::retry;
+
+#[retry(stop=attempts(3))]
+fn no_return_type() {}
+
+#[retry(stop=attempts(3))]
+fn unit_return_type() -> () {}
+
+#[retry(stop=attempts(3))]
+fn tuple_return_type() -> (i32, i32) {
+    (1, 2)
+}
+
+#[retry(stop=attempts(3))]
+fn reference_return_type() -> &'static str {
+    "test"
+}
+
+#[retry(stop=attempts(3),retry=if_errors(std::num::ParseIntError))]
+fn option_with_retry_errors() -> Option<i32> {
+    None
+}
+
+fn main() {}
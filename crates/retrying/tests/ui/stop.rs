@@ -0,0 +1,43 @@
+use retrying::retry;
+
+#[retry(stop=attempt(3))]
+fn misspelled_stop() -> Result<(), ()> {
+    Ok(())
+}
+
+#[retry(stop=attempts(0.5))]
+fn not_integer_attempts() -> Result<(), ()> {
+    Ok(())
+}
+
+#[retry(stop=attempts(5000000000))]
+fn too_big_attempts() -> Result<(), ()> {
+    Ok(())
+}
+
+#[retry(stop=attempts())]
+fn missing_attempts() -> Result<(), ()> {
+    Ok(())
+}
+
+#[retry(stop=duration("10 parsecs"))]
+fn wrong_duration() -> Result<(), ()> {
+    Ok(())
+}
+
+#[retry(stop=(attempts(3) & duration(1)))]
+fn wrong_operator() -> Result<(), ()> {
+    Ok(())
+}
+
+#[retry(stop=(attempts))]
+fn not_function_in_paren() -> Result<(), ()> {
+    Ok(())
+}
+
+#[retry(stop=attempts)]
+fn not_function() -> Result<(), ()> {
+    Ok(())
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use retrying::retry;
+
+trait Service {
+    #[retry(stop=attempts(3))]
+    fn call(&self) -> Result<(), ()>;
+}
+
+fn main() {}
@@ -0,0 +1,38 @@
+use retrying::retry;
+
+#[retry(wait=fixd(1))]
+fn misspelled_wait() -> Result<(), ()> {
+    Ok(())
+}
+
+#[retry(wait=fixed(1, 2))]
+fn fixed_with_two_arguments() -> Result<(), ()> {
+    Ok(())
+}
+
+#[retry(wait=random(min=1, mx=2))]
+fn misspelled_random_argument() -> Result<(), ()> {
+    Ok(())
+}
+
+#[retry(wait=random(min="1m", max="30s"))]
+fn random_min_greater_than_max() -> Result<(), ()> {
+    Ok(())
+}
+
+#[retry(wait=exponential(multiplyer=1))]
+fn misspelled_exponential_argument() -> Result<(), ()> {
+    Ok(())
+}
+
+#[retry(wait=exponential(exp_base=0))]
+fn zero_exp_base() -> Result<(), ()> {
+    Ok(())
+}
+
+#[retry(wait=exponential(min=std::time::Duration::ZERO))]
+fn path_as_duration() -> Result<(), ()> {
+    Ok(())
+}
+
+fn main() {}